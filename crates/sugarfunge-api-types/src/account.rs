@@ -1,4 +1,5 @@
 use crate::primitives::*;
+use crate::tx::TxParams;
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -9,7 +10,8 @@ pub struct CreateAccountOutput {
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct FundAccountInput {
-    pub seed: Seed,
+    #[serde(flatten)]
+    pub tx: TxParams,
     pub to: Account,
     pub amount: Balance,
 }
@@ -24,6 +26,7 @@ pub struct FundAccountOutput {
 #[derive(Serialize, Deserialize, Debug)]
 pub struct AccountBalanceInput {
     pub account: Account,
+    pub at: Option<BlockAt>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -34,6 +37,7 @@ pub struct AccountBalanceOutput {
 #[derive(Serialize, Deserialize, Debug)]
 pub struct AccountExistsInput {
     pub account: Account,
+    pub at: Option<BlockAt>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -61,7 +65,8 @@ pub struct Refund {
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct SetBalanceInput {
-    pub seed: Seed,
+    #[serde(flatten)]
+    pub tx: TxParams,
     pub to: Account,
     pub amount: Balance,
 }
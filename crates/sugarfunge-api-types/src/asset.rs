@@ -1,9 +1,11 @@
 use crate::primitives::*;
+use crate::tx::TxParams;
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct CreateClassInput {
-    pub seed: Seed,
+    #[serde(flatten)]
+    pub tx: TxParams,
     pub class_id: ClassId,
     pub metadata: serde_json::Value,
     pub owner: Account,
@@ -18,6 +20,7 @@ pub struct CreateClassOutput {
 #[derive(Serialize, Deserialize, Debug)]
 pub struct ClassInfoInput {
     pub class_id: ClassId,
+    pub at: Option<BlockAt>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -34,7 +37,8 @@ pub struct ClassInfoOutput {
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct CreateInput {
-    pub seed: Seed,
+    #[serde(flatten)]
+    pub tx: TxParams,
     pub class_id: ClassId,
     pub asset_id: AssetId,
     pub metadata: serde_json::Value,
@@ -51,6 +55,7 @@ pub struct CreateOutput {
 pub struct AssetInfoInput {
     pub class_id: ClassId,
     pub asset_id: AssetId,
+    pub at: Option<BlockAt>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -67,7 +72,8 @@ pub struct AssetInfoOutput {
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct UpdateMetadataInput {
-    pub seed: Seed,
+    #[serde(flatten)]
+    pub tx: TxParams,
     pub class_id: ClassId,
     pub asset_id: AssetId,
     pub metadata: serde_json::Value,
@@ -83,7 +89,8 @@ pub struct UpdateMetadataOutput {
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct MintInput {
-    pub seed: Seed,
+    #[serde(flatten)]
+    pub tx: TxParams,
     pub to: Account,
     pub class_id: ClassId,
     pub asset_id: AssetId,
@@ -101,7 +108,8 @@ pub struct MintOutput {
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct BurnInput {
-    pub seed: Seed,
+    #[serde(flatten)]
+    pub tx: TxParams,
     pub from: Account,
     pub class_id: ClassId,
     pub asset_id: AssetId,
@@ -122,6 +130,7 @@ pub struct AssetBalanceInput {
     pub account: Account,
     pub class_id: ClassId,
     pub asset_id: AssetId,
+    pub at: Option<BlockAt>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -134,11 +143,15 @@ pub struct AssetBalancesInput {
     pub account: Account,
     pub class_id: Option<ClassId>,
     pub asset_id: Option<AssetId>,
+    pub at: Option<BlockAt>,
+    pub limit: Option<u32>,
+    pub cursor: Option<Cursor>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct AssetBalancesOutput {
     pub balances: Vec<AssetBalanceItemOutput>,
+    pub next_cursor: Option<Cursor>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -150,7 +163,8 @@ pub struct AssetBalanceItemOutput {
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct TransferFromInput {
-    pub seed: Seed,
+    #[serde(flatten)]
+    pub tx: TxParams,
     pub from: Account,
     pub to: Account,
     pub class_id: ClassId,
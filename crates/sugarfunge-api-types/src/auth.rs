@@ -0,0 +1,26 @@
+use serde::{Deserialize, Serialize};
+
+/// Roles ordered by privilege, a role grants access to every route of the
+/// roles below it
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    ReadOnly,
+    User,
+    Operator,
+    Sudo,
+}
+
+impl std::str::FromStr for Role {
+    type Err = String;
+
+    fn from_str(role: &str) -> Result<Self, Self::Err> {
+        match role {
+            "read_only" => Ok(Role::ReadOnly),
+            "user" => Ok(Role::User),
+            "operator" => Ok(Role::Operator),
+            "sudo" => Ok(Role::Sudo),
+            _ => Err(format!("Unknown role {}", role)),
+        }
+    }
+}
@@ -1,9 +1,11 @@
 use crate::primitives::*;
+use crate::tx::TxParams;
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct RegisterInput {
-    pub seed: Seed,
+    #[serde(flatten)]
+    pub tx: TxParams,
     pub class_id: ClassId,
     pub metadata: serde_json::Value,
 }
@@ -16,7 +18,8 @@ pub struct RegisterOutput {
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct CreateInput {
-    pub seed: Seed,
+    #[serde(flatten)]
+    pub tx: TxParams,
     pub class_id: ClassId,
     pub owners: Vec<Account>,
     pub shares: Vec<Balance>,
@@ -32,7 +35,8 @@ pub struct CreateOutput {
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct SweepInput {
-    pub seed: Seed,
+    #[serde(flatten)]
+    pub tx: TxParams,
     pub bag: Account,
     pub to: Account,
 }
@@ -46,7 +50,8 @@ pub struct SweepOutput {
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct DepositInput {
-    pub seed: Seed,
+    #[serde(flatten)]
+    pub tx: TxParams,
     pub bag: Account,
     pub class_ids: Vec<ClassId>,
     pub asset_ids: Vec<Vec<AssetId>>,
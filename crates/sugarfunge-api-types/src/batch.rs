@@ -0,0 +1,65 @@
+use crate::asset::{BurnInput, MintInput, TransferFromInput};
+use crate::bag::DepositInput;
+use crate::bundle::MintBundleInput;
+use crate::pool::VoteInput;
+use crate::primitives::*;
+use crate::tx::{TxEvent, TxParams};
+use serde::{Deserialize, Serialize};
+
+/// How the calls of a batch are dispatched
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum BatchKind {
+    /// Every call succeeds or the whole batch is reverted (default)
+    #[default]
+    BatchAll,
+    /// Calls run until the first failure, the earlier ones are kept
+    Batch,
+    /// Every call runs, failures are reported per call
+    ForceBatch,
+}
+
+/// Input of an existing write route, named by its route. Signing and submission options of the
+/// call are ignored, the batch is signed and submitted as a whole.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(tag = "op")]
+pub enum BatchCall {
+    #[serde(rename = "asset/mint")]
+    AssetMint(MintInput),
+    #[serde(rename = "asset/burn")]
+    AssetBurn(BurnInput),
+    #[serde(rename = "asset/transfer_from")]
+    AssetTransferFrom(TransferFromInput),
+    #[serde(rename = "bag/deposit")]
+    BagDeposit(DepositInput),
+    #[serde(rename = "bundle/mint")]
+    BundleMint(MintBundleInput),
+    #[serde(rename = "fula/pool/vote")]
+    PoolVote(VoteInput),
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct BatchInput {
+    #[serde(flatten)]
+    pub tx: TxParams,
+    pub kind: Option<BatchKind>,
+    pub calls: Vec<BatchCall>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct BatchItemOutput {
+    pub index: u32,
+    pub success: bool,
+    /// Dispatch error of the call, or why it didn't run
+    pub error: Option<String>,
+    /// Events the call emitted
+    pub events: Vec<TxEvent>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct BatchOutput {
+    pub kind: BatchKind,
+    /// Whether every call succeeded
+    pub completed: bool,
+    pub items: Vec<BatchItemOutput>,
+}
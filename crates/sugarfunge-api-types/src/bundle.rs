@@ -1,4 +1,5 @@
 use crate::primitives::*;
+use crate::tx::TxParams;
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -10,7 +11,8 @@ pub struct BundleSchema {
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct RegisterBundleInput {
-    pub seed: Seed,
+    #[serde(flatten)]
+    pub tx: TxParams,
     pub class_id: ClassId,
     pub asset_id: AssetId,
     pub schema: BundleSchema,
@@ -27,7 +29,8 @@ pub struct RegisterBundleOutput {
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct MintBundleInput {
-    pub seed: Seed,
+    #[serde(flatten)]
+    pub tx: TxParams,
     pub from: Account,
     pub to: Account,
     pub bundle_id: BundleId,
@@ -45,7 +48,8 @@ pub struct MintBundleOutput {
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct BurnBundleInput {
-    pub seed: Seed,
+    #[serde(flatten)]
+    pub tx: TxParams,
     pub from: Account,
     pub to: Account,
     pub bundle_id: BundleId,
@@ -61,6 +65,11 @@ pub struct BurnBundleOutput {
     pub amount: Balance,
 }
 
+#[derive(Serialize, Deserialize, Debug)]
+pub struct GetBundlesInput {
+    pub at: Option<BlockAt>,
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct GetBundles {
     pub bundles: Vec<BundleItem>,
@@ -73,6 +82,11 @@ pub struct BundleItem {
     pub bundle_id: BundleId,
 }
 
+#[derive(Serialize, Deserialize, Debug)]
+pub struct GetBundlesDataInput {
+    pub at: Option<BlockAt>,
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct GetBundlesData {
     pub bundles: Vec<BundleDataItem>,
@@ -1,5 +1,6 @@
 use crate::primitives::*;
 use crate::sugarfunge::runtime_types::functionland_fula::ChallengeState;
+use crate::tx::TxParams;
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -23,7 +24,8 @@ impl Into<ChallengeStateValue> for ChallengeState {
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct GenerateChallengeInput {
-    pub seed: Seed,
+    #[serde(flatten)]
+    pub tx: TxParams,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -38,7 +40,8 @@ pub struct GenerateChallengeOutput {
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct VerifyChallengeInput {
-    pub seed: Seed,
+    #[serde(flatten)]
+    pub tx: TxParams,
     pub pool_id: PoolId,
     pub cids: Vec<Cid>,
     pub class_id: ClassId,
@@ -56,7 +59,8 @@ pub struct VerifyChallengeOutput {
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct MintLaborTokensInput {
-    pub seed: Seed,
+    #[serde(flatten)]
+    pub tx: TxParams,
     pub class_id: ClassId,
     pub asset_id: AssetId,
     pub amount: Balance,
@@ -76,6 +80,7 @@ pub struct MintLaborTokensOutput {
 #[derive(Serialize, Deserialize, Debug)]
 pub struct VerifyPendingChallengeInput {
     pub account: Account,
+    pub at: Option<BlockAt>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -88,6 +93,7 @@ pub struct VerifyPendingChallengeOutput {
 #[derive(Serialize, Deserialize, Debug)]
 pub struct VerifyFileSizeInput {
     pub account: Account,
+    pub at: Option<BlockAt>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -100,7 +106,8 @@ pub struct VerifyFileSizeOutput {
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct ProvideFileSizeInput {
-    pub seed: Seed,
+    #[serde(flatten)]
+    pub tx: TxParams,
     pub pool_id: PoolId,
     pub cids: Vec<Cid>,
     pub sizes: Vec<u64>,
@@ -116,9 +123,17 @@ pub struct ProvideFileSizeOutput {
 
 // Get Challenges
 
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct GetChallengesInput {
+    pub at: Option<BlockAt>,
+    pub limit: Option<u32>,
+    pub cursor: Option<Cursor>,
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct GetChallengesOutput {
     pub challenges: Vec<ChallengeData>,
+    pub next_cursor: Option<Cursor>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -130,9 +145,17 @@ pub struct ChallengeData {
 
 // Get Claim Data
 
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct GetClaimDataInput {
+    pub at: Option<BlockAt>,
+    pub limit: Option<u32>,
+    pub cursor: Option<Cursor>,
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct GetClaimDataOutput {
     pub claims: Vec<ClaimData>,
+    pub next_cursor: Option<Cursor>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
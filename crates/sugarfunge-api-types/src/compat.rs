@@ -0,0 +1,20 @@
+use serde::{Deserialize, Serialize};
+
+/// Calls and storage entries of a pallet that differ between the compiled and the live runtime
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct PalletCompat {
+    pub pallet: String,
+    /// The pallet is not in the live runtime at all
+    pub missing: bool,
+    pub calls: Vec<String>,
+    pub storage: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct CompatOutput {
+    /// Spec version of the live runtime the check ran against
+    pub spec_version: u32,
+    pub compatible: bool,
+    /// Pallets whose routes are refused until the node runs a compatible runtime
+    pub incompatible: Vec<PalletCompat>,
+}
@@ -1,6 +1,7 @@
 use serde::{Deserialize, Serialize};
 
 use crate::primitives::*;
+use crate::tx::TxParams;
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct ContractTransactionInput {
@@ -24,9 +25,12 @@ pub struct ContractAllowanceOutput {
     pub allowance: u128,
 }
 
+/// Only blocking mode waiting for finalization is supported, the contract mint needs the result
+/// of the bundle mint
 #[derive(Serialize, Deserialize, Debug)]
 pub struct ConvertFulaInput {
-    pub seed: Seed,
+    #[serde(flatten)]
+    pub tx: TxParams,
     pub wallet_account: String,
     pub amount: Balance,
 }
@@ -0,0 +1,49 @@
+use crate::primitives::*;
+use crate::tx::{TxEvent, TxParams};
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct DynamicTxInput {
+    #[serde(flatten)]
+    pub tx: TxParams,
+    pub pallet: String,
+    pub call: String,
+    /// Call arguments, an object keyed by field name or an array in field order
+    #[serde(default)]
+    pub args: serde_json::Value,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct DynamicTxOutput {
+    pub events: Vec<TxEvent>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct DynamicStorageInput {
+    pub pallet: String,
+    pub entry: String,
+    /// Map keys in order, fewer keys than the map has list every entry under them
+    #[serde(default)]
+    pub keys: Vec<serde_json::Value>,
+    pub at: Option<BlockAt>,
+    pub limit: Option<u32>,
+    pub cursor: Option<Cursor>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct DynamicStorageEntry {
+    /// Hex encoded storage key
+    pub key: String,
+    pub value: serde_json::Value,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct DynamicStorageOutput {
+    /// Value under the keys, set when every key was given
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<serde_json::Value>,
+    /// Entries under the keys, set when some keys were left out
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub entries: Option<Vec<DynamicStorageEntry>>,
+    pub next_cursor: Option<Cursor>,
+}
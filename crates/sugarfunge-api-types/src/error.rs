@@ -0,0 +1,37 @@
+use serde::{Deserialize, Serialize};
+
+/// Broad class of an error, for clients that don't know a specific code
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCategory {
+    Input,
+    Auth,
+    NotFound,
+    Dispatch,
+    Transaction,
+    Transport,
+    Decoding,
+    Upstream,
+    /// The route is refused until the node runs a runtime compatible with the api
+    Unavailable,
+    Internal,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ErrorDetails {
+    pub code: String,
+    pub category: ErrorCategory,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pallet: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub variant: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub docs: Option<String>,
+}
+
+/// Body of every error response
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ErrorOutput {
+    pub error: ErrorDetails,
+}
@@ -1,11 +1,12 @@
-use crate::{challenge::ChallengeStateValue, primitives::*};
+use crate::{challenge::ChallengeStateValue, primitives::*, tx::TxParams};
 use serde::{Deserialize, Serialize};
 
 // VERIFY MANIFEST STRUCTS
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct VerifyManifestsInput {
-    pub seed: Seed,
+    #[serde(flatten)]
+    pub tx: TxParams,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -19,7 +20,8 @@ pub struct VerifyManifestsOutput {
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct UploadManifestInput {
-    pub seed: Seed,
+    #[serde(flatten)]
+    pub tx: TxParams,
     pub manifest_metadata: serde_json::Value,
     pub cid: Cid,
     pub pool_id: PoolId,
@@ -38,7 +40,8 @@ pub struct UploadManifestOutput {
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct BatchUploadManifestInput {
-    pub seed: Seed,
+    #[serde(flatten)]
+    pub tx: TxParams,
     pub manifest_metadata: Vec<serde_json::Value>,
     pub cid: Vec<Cid>,
     pub pool_id: Vec<PoolId>,
@@ -56,7 +59,8 @@ pub struct BatchUploadManifestOutput {
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct UpdateManifestInput {
-    pub seed: Seed,
+    #[serde(flatten)]
+    pub tx: TxParams,
     pub cid: Cid,
     pub pool_id: PoolId,
     pub active_days: i32,
@@ -78,7 +82,8 @@ pub struct UpdatedManifestOutput {
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct StorageManifestInput {
-    pub seed: Seed,
+    #[serde(flatten)]
+    pub tx: TxParams,
     pub cid: Cid,
     pub pool_id: PoolId,
 }
@@ -94,7 +99,8 @@ pub struct StorageManifestOutput {
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct BatchStorageManifestInput {
-    pub seed: Seed,
+    #[serde(flatten)]
+    pub tx: TxParams,
     pub pool_id: PoolId,
     pub cid: Vec<Cid>,
 }
@@ -110,7 +116,8 @@ pub struct BatchStorageManifestOutput {
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct RemoveManifestInput {
-    pub seed: Seed,
+    #[serde(flatten)]
+    pub tx: TxParams,
     pub cid: Cid,
     pub pool_id: PoolId,
 }
@@ -125,7 +132,8 @@ pub struct RemoveManifestOutput {
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct BatchRemoveManifestInput {
-    pub seed: Seed,
+    #[serde(flatten)]
+    pub tx: TxParams,
     pub pool_id: Vec<PoolId>,
     pub cid: Vec<Cid>,
 }
@@ -140,7 +148,8 @@ pub struct BatchRemoveManifestOutput {
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct RemoveStoringManifestInput {
-    pub seed: Seed,
+    #[serde(flatten)]
+    pub tx: TxParams,
     pub cid: Cid,
     pub pool_id: PoolId,
 }
@@ -155,7 +164,8 @@ pub struct RemoveStoringManifestOutput {
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct BatchRemoveStoringManifestInput {
-    pub seed: Seed,
+    #[serde(flatten)]
+    pub tx: TxParams,
     pub pool_id: PoolId,
     pub cid: Vec<Cid>,
 }
@@ -173,10 +183,14 @@ pub struct GetAllManifestsInput {
     pub pool_id: Option<PoolId>,
     pub uploader: Option<Account>,
     pub storer: Option<Account>,
+    pub at: Option<BlockAt>,
+    pub limit: Option<u32>,
+    pub cursor: Option<Cursor>,
 }
 #[derive(Serialize, Deserialize, Debug)]
 pub struct GetAllManifestsOutput {
     pub manifests: Vec<Manifest>,
+    pub next_cursor: Option<Cursor>,
 }
 
 // GET MANIFESTS STORER DATA STRUCTS
@@ -185,6 +199,7 @@ pub struct GetAllManifestsOutput {
 pub struct GetAllManifestsStorerDataInput {
     pub pool_id: Option<PoolId>,
     pub storer: Option<Account>,
+    pub at: Option<BlockAt>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -208,11 +223,15 @@ pub struct ManifestStorageData {
 #[derive(Serialize, Deserialize, Debug)]
 pub struct GetAvailableManifestsInput {
     pub pool_id: Option<PoolId>,
+    pub at: Option<BlockAt>,
+    pub limit: Option<u32>,
+    pub cursor: Option<Cursor>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct GetAvailableManifestsOutput {
     pub manifests: Vec<ManifestAvailable>,
+    pub next_cursor: Option<Cursor>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -244,12 +263,14 @@ pub struct GetAvailableManifestsBatchInput {
     pub pool_id: PoolId,
     pub uploader: Account,
     pub cids: Vec<Cid>,
+    pub at: Option<BlockAt>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct GetAvailableManifestsAllaccountsBatchInput {
     pub pool_id: PoolId,
     pub cids: Vec<Cid>,
+    pub at: Option<BlockAt>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -0,0 +1,99 @@
+use crate::auth::Role;
+use crate::primitives::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct KeyInfo {
+    pub key_id: KeyId,
+    pub alias: String,
+    pub account: Account,
+    pub locked: bool,
+    /// Api key names or jwt subjects allowed to sign with the key
+    pub principals: Vec<String>,
+    /// Callers with this role or above can sign with the key, whatever their principal
+    pub min_role: Role,
+}
+
+// CREATE KEY
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct CreateKeyInput {
+    pub alias: String,
+    pub password: String,
+    /// Defaults to the caller creating the key
+    #[serde(default)]
+    pub principals: Vec<String>,
+    /// Defaults to `sudo`
+    pub min_role: Option<Role>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct CreateKeyOutput {
+    pub key: KeyInfo,
+}
+
+// IMPORT KEY
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ImportKeyInput {
+    pub alias: String,
+    pub seed: Seed,
+    pub password: String,
+    /// Defaults to the caller importing the key
+    #[serde(default)]
+    pub principals: Vec<String>,
+    /// Defaults to `sudo`
+    pub min_role: Option<Role>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ImportKeyOutput {
+    pub key: KeyInfo,
+}
+
+// LIST KEYS
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ListKeysOutput {
+    pub keys: Vec<KeyInfo>,
+}
+
+// UNLOCK KEY
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct UnlockKeyInput {
+    pub key: KeyId,
+    pub password: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct UnlockKeyOutput {
+    pub key: KeyInfo,
+}
+
+// BIND KEY
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct BindKeyInput {
+    pub key: KeyId,
+    pub password: String,
+    pub principals: Vec<String>,
+    pub min_role: Role,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct BindKeyOutput {
+    pub key: KeyInfo,
+}
+
+// LOCK KEY
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct LockKeyInput {
+    pub key: KeyId,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct LockKeyOutput {
+    pub key: KeyInfo,
+}
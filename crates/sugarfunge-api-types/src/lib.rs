@@ -9,15 +9,30 @@
     derive_for_type(path = "sugarfunge_bag::pallet::Event", derive = "serde::Serialize")
 )]
 pub mod sugarfunge {}
+
+/// Metadata the `sugarfunge` module was generated from, checked against the live runtime
+pub const SUGARFUNGE_METADATA: &[u8] = include_bytes!("../sugarfunge_metadata.scale");
+
 pub mod account;
 pub mod asset;
+pub mod auth;
 pub mod bag;
+pub mod batch;
 pub mod bundle;
 pub mod challenge;
+pub mod compat;
 pub mod contract;
+pub mod dynamic;
+pub mod error;
 pub mod fula;
+pub mod keystore;
 pub mod market;
+pub mod multisig;
 pub mod online;
 pub mod pool;
 pub mod primitives;
+pub mod proxy;
+pub mod storage_key;
+pub mod subscription;
+pub mod tx;
 pub mod validator;
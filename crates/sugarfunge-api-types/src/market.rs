@@ -1,4 +1,5 @@
 use crate::primitives::*;
+use crate::tx::TxParams;
 use serde::{Deserialize, Serialize};
 
 use crate::sugarfunge::runtime_types::sugarfunge_market;
@@ -208,7 +209,8 @@ pub struct Rates {
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct CreateMarketInput {
-    pub seed: Seed,
+    #[serde(flatten)]
+    pub tx: TxParams,
     pub market_id: MarketId,
 }
 
@@ -220,7 +222,8 @@ pub struct CreateMarketOutput {
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct CreateMarketRateInput {
-    pub seed: Seed,
+    #[serde(flatten)]
+    pub tx: TxParams,
     pub market_id: MarketId,
     pub market_rate_id: MarketId,
     pub rates: Rates,
@@ -234,7 +237,8 @@ pub struct CreateMarketRateOutput {
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct DepositAssetsInput {
-    pub seed: Seed,
+    #[serde(flatten)]
+    pub tx: TxParams,
     pub market_id: MarketId,
     pub market_rate_id: MarketId,
     pub amount: Balance,
@@ -252,7 +256,8 @@ pub struct DepositAssetsOutput {
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct ExchangeAssetsInput {
-    pub seed: Seed,
+    #[serde(flatten)]
+    pub tx: TxParams,
     pub market_id: MarketId,
     pub market_rate_id: MarketId,
     pub amount: Balance,
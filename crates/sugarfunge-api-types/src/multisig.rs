@@ -0,0 +1,81 @@
+use crate::batch::BatchCall;
+use crate::primitives::*;
+use crate::tx::{TxEvent, TxParams};
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct MultisigAccountInput {
+    pub signatories: Vec<Account>,
+    pub threshold: u16,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct MultisigAccountOutput {
+    pub account: Account,
+    /// Signatories in the order the account is derived from
+    pub signatories: Vec<Account>,
+    pub threshold: u16,
+}
+
+/// Approval of a call by one signatory of a multisig account. The account is derived from the
+/// signer, the other signatories and the threshold.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct MultisigCallInput {
+    #[serde(flatten)]
+    pub tx: TxParams,
+    pub other_signatories: Vec<Account>,
+    pub threshold: u16,
+    /// Call dispatched from the multisig account once the threshold is reached
+    pub call: BatchCall,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum MultisigState {
+    /// The operation was opened with its first approval
+    Opened,
+    /// The approval was recorded, the threshold is not reached yet
+    Approved,
+    /// The threshold was reached and the call dispatched
+    Executed,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct MultisigCallOutput {
+    pub account: Account,
+    pub call_hash: String,
+    pub state: MultisigState,
+    /// Dispatch error of the call, when it was executed and failed
+    pub error: Option<String>,
+    pub events: Vec<TxEvent>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct MultisigPendingInput {
+    pub account: Account,
+    pub at: Option<BlockAt>,
+    pub limit: Option<u32>,
+    pub cursor: Option<Cursor>,
+}
+
+/// Block and extrinsic index an operation was opened at
+#[derive(Serialize, Deserialize, Debug)]
+pub struct MultisigTimepoint {
+    pub height: u32,
+    pub index: u32,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct MultisigPending {
+    pub call_hash: String,
+    pub when: MultisigTimepoint,
+    pub deposit: Balance,
+    pub depositor: Account,
+    pub approvals: Vec<Account>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct MultisigPendingOutput {
+    pub pending: Vec<MultisigPending>,
+    pub next_cursor: Option<Cursor>,
+}
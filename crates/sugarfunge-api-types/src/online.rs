@@ -1,15 +1,31 @@
+use crate::primitives::*;
 use serde::{Deserialize, Serialize};
 
+#[derive(Serialize, Deserialize, Debug)]
+pub struct GetAuthoredBlocksInput {
+    pub at: Option<BlockAt>,
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct GetAuthoredBlocks {
     pub validators: Vec<String>,
 }
 
+#[derive(Serialize, Deserialize, Debug)]
+pub struct GetHeartbeatsInput {
+    pub at: Option<BlockAt>,
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct GetHeartbeats {
     pub validators: Vec<String>,
 }
 
+#[derive(Serialize, Deserialize, Debug)]
+pub struct HeartbeatTimeInput {
+    pub at: Option<BlockAt>,
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct HeartbeatTime {
     pub time: Option<u32>,
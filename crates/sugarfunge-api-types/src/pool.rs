@@ -1,11 +1,13 @@
 use crate::primitives::*;
+use crate::tx::TxParams;
 use serde::{Deserialize, Serialize};
 
 // CREATE POOL
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct CreatePoolInput {
-    pub seed: Seed,
+    #[serde(flatten)]
+    pub tx: TxParams,
     pub pool_name: Name,
     pub peer_id: PeerId,
     pub region: String,
@@ -21,7 +23,8 @@ pub struct CreatePoolOutput {
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct LeavePoolInput {
-    pub seed: Seed,
+    #[serde(flatten)]
+    pub tx: TxParams,
     pub pool_id: PoolId,
     pub target_account: Option<Account>, // Optional target account to be removed
 }
@@ -36,7 +39,8 @@ pub struct LeavePoolOutput {
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct JoinPoolInput {
-    pub seed: Seed,
+    #[serde(flatten)]
+    pub tx: TxParams,
     pub pool_id: PoolId,
     pub peer_id: PeerId,
 }
@@ -51,7 +55,8 @@ pub struct JoinPoolOutput {
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct CancelJoinPoolInput {
-    pub seed: Seed,
+    #[serde(flatten)]
+    pub tx: TxParams,
     pub pool_id: PoolId,
     pub target_account: Option<Account>, // Optional target account to be removed
 }
@@ -66,7 +71,8 @@ pub struct CancelJoinPoolOutput {
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct VoteInput {
-    pub seed: Seed,
+    #[serde(flatten)]
+    pub tx: TxParams,
     pub pool_id: PoolId,
     pub account: Account,
     pub vote_value: bool,
@@ -85,6 +91,7 @@ pub struct VoteOutput {
 #[derive(Serialize, Deserialize, Debug)]
 pub struct GetAllPoolInput {
     pub region: Option<String>,
+    pub at: Option<BlockAt>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -109,11 +116,15 @@ pub struct GetAllPoolUsersInput {
     pub account: Option<Account>,
     pub pool_id: Option<PoolId>,
     pub request_pool_id: Option<PoolId>, // New field added for additional filter
+    pub at: Option<BlockAt>,
+    pub limit: Option<u32>,
+    pub cursor: Option<Cursor>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct GetAllPoolUsersOutput {
     pub users: Vec<PoolUserData>,
+    pub next_cursor: Option<Cursor>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -130,6 +141,7 @@ pub struct PoolUserData {
 pub struct GetAllPoolRequestInput {
     pub pool_id: Option<PoolId>,
     pub account: Option<Account>,
+    pub at: Option<BlockAt>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -1,5 +1,6 @@
 use std::{ops::Div, str::FromStr};
 
+use serde::de::{self, Deserializer, Visitor};
 use serde::{Deserialize, Serialize};
 
 use sp_core;
@@ -29,6 +30,100 @@ impl Seed {
     }
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, Deref, DerefMut)]
+pub struct KeyId(String);
+
+impl From<String> for KeyId {
+    fn from(key_id: String) -> KeyId {
+        KeyId(key_id)
+    }
+}
+
+impl From<&KeyId> for String {
+    fn from(key_id: &KeyId) -> String {
+        key_id.0.clone()
+    }
+}
+
+impl KeyId {
+    pub fn as_str(&self) -> &str {
+        self.0.as_str()
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Deref, DerefMut)]
+pub struct TxId(String);
+
+impl From<String> for TxId {
+    fn from(tx_id: String) -> TxId {
+        TxId(tx_id)
+    }
+}
+
+impl From<&TxId> for String {
+    fn from(tx_id: &TxId) -> String {
+        tx_id.0.clone()
+    }
+}
+
+impl TxId {
+    pub fn as_str(&self) -> &str {
+        self.0.as_str()
+    }
+}
+
+/// Hex encoded storage key a list endpoint resumes from
+#[derive(Serialize, Deserialize, Clone, Debug, Deref, DerefMut)]
+pub struct Cursor(String);
+
+impl From<String> for Cursor {
+    fn from(cursor: String) -> Cursor {
+        Cursor(cursor)
+    }
+}
+
+impl From<&Cursor> for String {
+    fn from(cursor: &Cursor) -> String {
+        cursor.0.clone()
+    }
+}
+
+impl Cursor {
+    pub fn as_str(&self) -> &str {
+        self.0.as_str()
+    }
+}
+
+/// Id of an unsigned payload waiting for its external signature
+#[derive(Serialize, Deserialize, Clone, Debug, Deref, DerefMut)]
+pub struct PayloadId(String);
+
+impl From<String> for PayloadId {
+    fn from(payload_id: String) -> PayloadId {
+        PayloadId(payload_id)
+    }
+}
+
+impl From<&PayloadId> for String {
+    fn from(payload_id: &PayloadId) -> String {
+        payload_id.0.clone()
+    }
+}
+
+impl PayloadId {
+    pub fn as_str(&self) -> &str {
+        self.0.as_str()
+    }
+}
+
+/// Block a read is served from, by number or hash
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(untagged)]
+pub enum BlockAt {
+    Number(u32),
+    Hash(String),
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, Deref, DerefMut)]
 pub struct Account(String);
 
@@ -137,9 +232,18 @@ impl From<AssetId> for u64 {
     }
 }
 
-#[derive(Serialize, Deserialize, Clone, Copy, Debug, Deref, DerefMut)]
+#[derive(Serialize, Clone, Copy, Debug, Deref, DerefMut)]
 pub struct Balance(u128);
 
+impl<'de> Deserialize<'de> for Balance {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer
+            .deserialize_any(IntegerVisitor)
+            .and_then(|n| u128::try_from(n).map_err(de::Error::custom))
+            .map(Balance)
+    }
+}
+
 impl From<u128> for Balance {
     fn from(id: u128) -> Balance {
         Balance(id)
@@ -152,9 +256,15 @@ impl From<Balance> for u128 {
     }
 }
 
-#[derive(Serialize, Deserialize, Clone, Copy, Debug, Deref, DerefMut)]
+#[derive(Serialize, Clone, Copy, Debug, Deref, DerefMut)]
 pub struct Amount(i128);
 
+impl<'de> Deserialize<'de> for Amount {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(IntegerVisitor).map(Amount)
+    }
+}
+
 impl From<i128> for Amount {
     fn from(id: i128) -> Amount {
         Amount(id)
@@ -167,6 +277,44 @@ impl From<Amount> for i128 {
     }
 }
 
+/// Reads a 128 bit integer from a number or a decimal string. Inputs with flattened fields buffer
+/// json numbers as 64 bit integers or floats, larger values have to be sent as strings.
+struct IntegerVisitor;
+
+impl<'de> Visitor<'de> for IntegerVisitor {
+    type Value = i128;
+
+    fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str("an integer, or a decimal string above 64 bits")
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<i128, E> {
+        Ok(v.into())
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<i128, E> {
+        Ok(v.into())
+    }
+
+    fn visit_i128<E: de::Error>(self, v: i128) -> Result<i128, E> {
+        Ok(v)
+    }
+
+    fn visit_u128<E: de::Error>(self, v: u128) -> Result<i128, E> {
+        i128::try_from(v).map_err(E::custom)
+    }
+
+    fn visit_f64<E: de::Error>(self, _v: f64) -> Result<i128, E> {
+        Err(E::custom(
+            "integer is not exact, send integers above 64 bits as decimal strings",
+        ))
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<i128, E> {
+        v.parse().map_err(E::custom)
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, Deref, DerefMut)]
 pub struct BundleId(String);
 
@@ -0,0 +1,28 @@
+use crate::primitives::*;
+use crate::tx::TxParams;
+use serde::{Deserialize, Serialize};
+
+/// Registers or revokes a delegate of the signer. Once registered the delegate can submit the
+/// calls of any write route for the signer with `on_behalf_of`.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ProxyInput {
+    #[serde(flatten)]
+    pub tx: TxParams,
+    pub delegate: Account,
+    /// Proxy type of the runtime limiting the calls of the delegate, `Any` by default
+    pub proxy_type: Option<String>,
+    /// Blocks the delegate has to announce its calls in advance, none by default
+    pub delay: Option<u32>,
+    /// Revoke the delegate instead of registering it
+    #[serde(default)]
+    pub revoke: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ProxyOutput {
+    pub account: Account,
+    pub delegate: Account,
+    pub proxy_type: String,
+    pub delay: u32,
+    pub revoked: bool,
+}
@@ -0,0 +1,210 @@
+use codec::Decode;
+use std::fmt;
+use subxt::metadata::types::{StorageEntryType, StorageHasher};
+use subxt::storage::StorageAddress;
+use subxt::Metadata;
+
+/// Length of the `twox_128(pallet) ++ twox_128(entry)` prefix every storage key starts with
+const PREFIX_LEN: usize = 32;
+
+#[derive(Debug)]
+pub enum StorageKeyError {
+    /// The pallet or storage entry is not in the runtime metadata
+    NotFound(String),
+    /// The storage entry is a plain value, it has no key to decode
+    NotAMap(String),
+    /// The key was hashed without its value appended, so it can't be recovered
+    Opaque(StorageHasher),
+    /// More key parts were requested than the storage entry has
+    TooManyParts(usize),
+    /// The key is shorter than its layout or a part failed to decode
+    Codec(String),
+}
+
+impl fmt::Display for StorageKeyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StorageKeyError::NotFound(name) => write!(f, "Storage entry {} not found", name),
+            StorageKeyError::NotAMap(name) => write!(f, "Storage entry {} is not a map", name),
+            StorageKeyError::Opaque(hasher) => {
+                write!(f, "Key hashed with {:?} can't be decoded", hasher)
+            }
+            StorageKeyError::TooManyParts(parts) => {
+                write!(f, "Storage entry has fewer than {} key parts", parts)
+            }
+            StorageKeyError::Codec(e) => write!(f, "Invalid storage key: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for StorageKeyError {}
+
+/// Decodes the keys of a storage map, using the hashers the runtime metadata declares for it
+/// rather than fixed byte offsets.
+#[derive(Clone, Debug)]
+pub struct StorageKeyDecoder {
+    hashers: Vec<StorageHasher>,
+}
+
+impl StorageKeyDecoder {
+    /// Decoder for the storage map `address` points at,
+    /// e.g. `sugarfunge::storage().fula().manifests_iter()`
+    pub fn new<A: StorageAddress>(
+        metadata: &Metadata,
+        address: &A,
+    ) -> Result<Self, StorageKeyError> {
+        let name = format!("{}::{}", address.pallet_name(), address.entry_name());
+        let entry = metadata
+            .pallet_by_name(address.pallet_name())
+            .and_then(|pallet| pallet.storage())
+            .and_then(|storage| storage.entry_by_name(address.entry_name()))
+            .ok_or_else(|| StorageKeyError::NotFound(name.clone()))?;
+        match entry.entry_type() {
+            StorageEntryType::Map { hashers, .. } => Ok(StorageKeyDecoder {
+                hashers: hashers.clone(),
+            }),
+            StorageEntryType::Plain(_) => Err(StorageKeyError::NotAMap(name)),
+        }
+    }
+
+    /// Decodes the leading key parts of a full storage key as a tuple, `(A,)`, `(A, B)` or
+    /// `(A, B, C)`. Trailing parts that aren't requested are ignored.
+    pub fn decode<K: StorageKeyParts>(&self, key: &[u8]) -> Result<K, StorageKeyError> {
+        if key.len() < PREFIX_LEN {
+            return Err(StorageKeyError::Codec("key shorter than its prefix".into()));
+        }
+        if K::LEN > self.hashers.len() {
+            return Err(StorageKeyError::TooManyParts(K::LEN));
+        }
+        let mut input = &key[PREFIX_LEN..];
+        K::decode_parts(&self.hashers, &mut input)
+    }
+}
+
+/// Decodes one key part, skipping the hash its hasher prepends to it
+fn decode_part<T: Decode>(hasher: &StorageHasher, input: &mut &[u8]) -> Result<T, StorageKeyError> {
+    let hash_len = match hasher {
+        StorageHasher::Blake2_128Concat => 16,
+        StorageHasher::Twox64Concat => 8,
+        StorageHasher::Identity => 0,
+        hasher => return Err(StorageKeyError::Opaque(hasher.clone())),
+    };
+    if input.len() < hash_len {
+        return Err(StorageKeyError::Codec("key shorter than its hash".into()));
+    }
+    *input = &input[hash_len..];
+    T::decode(input).map_err(|e| StorageKeyError::Codec(e.to_string()))
+}
+
+/// Tuple of storage key parts decoded by `StorageKeyDecoder`
+pub trait StorageKeyParts: Sized {
+    const LEN: usize;
+
+    fn decode_parts(hashers: &[StorageHasher], input: &mut &[u8]) -> Result<Self, StorageKeyError>;
+}
+
+macro_rules! impl_storage_key_parts {
+    ($len:expr; $($part:ident: $index:tt),+) => {
+        impl<$($part: Decode),+> StorageKeyParts for ($($part,)+) {
+            const LEN: usize = $len;
+
+            fn decode_parts(
+                hashers: &[StorageHasher],
+                input: &mut &[u8],
+            ) -> Result<Self, StorageKeyError> {
+                Ok(($(decode_part::<$part>(&hashers[$index], input)?,)+))
+            }
+        }
+    };
+}
+
+impl_storage_key_parts!(1; A: 0);
+impl_storage_key_parts!(2; A: 0, B: 1);
+impl_storage_key_parts!(3; A: 0, B: 1, C: 2);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use codec::Encode;
+    use sp_core::hashing::{blake2_128, twox_128, twox_64};
+
+    fn prefix() -> Vec<u8> {
+        [twox_128(b"Fula"), twox_128(b"Manifests")].concat()
+    }
+
+    fn blake2_128_concat<T: Encode>(part: &T) -> Vec<u8> {
+        let encoded = part.encode();
+        [&blake2_128(&encoded)[..], &encoded].concat()
+    }
+
+    fn twox_64_concat<T: Encode>(part: &T) -> Vec<u8> {
+        let encoded = part.encode();
+        [&twox_64(&encoded)[..], &encoded].concat()
+    }
+
+    fn decoder(hashers: &[StorageHasher]) -> StorageKeyDecoder {
+        StorageKeyDecoder {
+            hashers: hashers.to_vec(),
+        }
+    }
+
+    #[test]
+    fn decodes_each_concat_hasher() {
+        let keys = decoder(&[
+            StorageHasher::Blake2_128Concat,
+            StorageHasher::Twox64Concat,
+            StorageHasher::Identity,
+        ]);
+        let cid = String::from("bafybeigdyrzt5sfp7udm7hu76uh7y26nf3efuylqabf3oclgtqy55fbzdi");
+        let key = [
+            prefix(),
+            blake2_128_concat(&7u32),
+            twox_64_concat(&[1u8; 32]),
+            cid.encode(),
+        ]
+        .concat();
+
+        let decoded: (u32, [u8; 32], String) = keys.decode(&key).unwrap();
+        assert_eq!(decoded, (7, [1; 32], cid));
+    }
+
+    #[test]
+    fn decodes_leading_parts_only() {
+        let keys = decoder(&[StorageHasher::Twox64Concat, StorageHasher::Blake2_128Concat]);
+        let key = [prefix(), twox_64_concat(&42u64), blake2_128_concat(&9u64)].concat();
+
+        let (class_id,): (u64,) = keys.decode(&key).unwrap();
+        assert_eq!(class_id, 42);
+    }
+
+    #[test]
+    fn rejects_truncated_keys() {
+        let keys = decoder(&[StorageHasher::Blake2_128Concat, StorageHasher::Twox64Concat]);
+        let key = [prefix(), blake2_128_concat(&7u32), twox_64_concat(&42u64)].concat();
+
+        // Cut inside the value of the last part, then inside its hash, then inside the prefix
+        for len in [key.len() - 4, PREFIX_LEN + 20 + 4, PREFIX_LEN - 1] {
+            let result = keys.decode::<(u32, u64)>(&key[..len]);
+            assert!(
+                matches!(result, Err(StorageKeyError::Codec(_))),
+                "length {}",
+                len
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_opaque_hashers_and_extra_parts() {
+        let keys = decoder(&[StorageHasher::Blake2_128]);
+        let key = [prefix(), blake2_128(&7u32.encode()).to_vec()].concat();
+
+        assert!(matches!(
+            keys.decode::<(u32,)>(&key),
+            Err(StorageKeyError::Opaque(StorageHasher::Blake2_128))
+        ));
+        assert!(matches!(
+            keys.decode::<(u32, u32)>(&key),
+            Err(StorageKeyError::TooManyParts(2))
+        ));
+    }
+}
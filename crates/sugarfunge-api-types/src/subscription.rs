@@ -0,0 +1,94 @@
+use crate::primitives::*;
+use crate::tx::TxEvent;
+use serde::{Deserialize, Serialize};
+
+/// Message a client sends over `/ws`
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum SubscriptionRequest {
+    Subscribe(SubscribeInput),
+    Unsubscribe { id: String },
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SubscribeInput {
+    /// Id of the subscription in the messages of its events, one is generated when left out
+    pub id: Option<String>,
+    pub topics: Vec<EventTopic>,
+    #[serde(default)]
+    pub filter: EventFilter,
+    /// Replay the events of the finalized blocks from this one before the new ones
+    pub from_block: Option<u32>,
+}
+
+/// Events of a pallet, every event of the pallet when `events` is empty
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct EventTopic {
+    pub pallet: String,
+    #[serde(default)]
+    pub events: Vec<String>,
+}
+
+/// Only events with every given value somewhere in their fields are sent
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct EventFilter {
+    pub account: Option<Account>,
+    pub class_id: Option<ClassId>,
+    pub asset_id: Option<AssetId>,
+    pub pool_id: Option<PoolId>,
+    pub cid: Option<Cid>,
+}
+
+/// Message the server sends over `/ws`
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SubscriptionMessage {
+    Subscribed {
+        id: String,
+    },
+    Unsubscribed {
+        id: String,
+    },
+    /// The replay reached the finalized head, the next events are live
+    Replayed {
+        id: String,
+        block_number: u32,
+    },
+    /// An event of a subscription, with the finalized block it was emitted in and its index
+    /// among the events of the block
+    Event {
+        id: String,
+        block_number: u32,
+        block_hash: String,
+        index: u32,
+        event: TxEvent,
+    },
+    Error {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        id: Option<String>,
+        message: String,
+    },
+}
+
+/// Query of the `/events` server-sent events stream
+#[derive(Serialize, Deserialize, Debug)]
+pub struct EventStreamInput {
+    /// Comma separated topics, `Pallet` for every event of a pallet or `Pallet.Event`
+    pub topics: String,
+    pub account: Option<Account>,
+    pub class_id: Option<ClassId>,
+    pub asset_id: Option<AssetId>,
+    pub pool_id: Option<PoolId>,
+    pub cid: Option<Cid>,
+    /// Replay the events of the finalized blocks from this one, a `Last-Event-ID` header wins
+    pub from_block: Option<u32>,
+}
+
+/// Data of an event of the `/events` stream, its id is `<block_number>:<index>`
+#[derive(Serialize, Deserialize, Debug)]
+pub struct StreamEvent {
+    pub block_number: u32,
+    pub block_hash: String,
+    pub index: u32,
+    pub event: TxEvent,
+}
@@ -0,0 +1,148 @@
+use crate::error::ErrorDetails;
+use crate::primitives::*;
+use serde::{Deserialize, Serialize};
+
+/// How a write request waits for its transaction
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TxMode {
+    /// Respond once the transaction is finalized (default)
+    #[default]
+    Blocking,
+    /// Respond as soon as the transaction is in the pool, track it with `tx/status`
+    Async,
+    /// Respond with the expected fee and dispatch result, nothing is submitted
+    DryRun,
+}
+
+/// Confirmation level a write request waits for before responding
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum WaitFor {
+    /// Respond once the transaction is in the pool, like `TxMode::Async`
+    None,
+    /// Respond once the transaction is in a best block
+    InBlock,
+    /// Respond once the transaction is finalized (default)
+    #[default]
+    Finalized,
+}
+
+/// Signing and submission fields every write input takes
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct TxParams {
+    pub seed: Option<Seed>,
+    /// Keystore key id or alias, signs instead of `seed`
+    pub key: Option<KeyId>,
+    /// External signer, the payload to sign is returned instead of a submitted transaction
+    pub signer: Option<Account>,
+    /// Account the call is dispatched for through `Proxy::proxy`
+    pub on_behalf_of: Option<Account>,
+    pub mode: Option<TxMode>,
+    pub wait_for: Option<WaitFor>,
+    pub webhook: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TxState {
+    Ready,
+    InBlock,
+    Finalized,
+    Dropped,
+    Invalid,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct TxEvent {
+    pub pallet: String,
+    pub variant: String,
+    pub fields: serde_json::Value,
+}
+
+/// Output of a write route whose transaction was included, with the confirmation level reached
+/// and the block the transaction is in
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Confirmed<T> {
+    #[serde(flatten)]
+    pub output: T,
+    pub confirmation: WaitFor,
+    pub block_hash: String,
+}
+
+// SUBMITTED TX
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SubmittedTxOutput {
+    pub tx_id: TxId,
+    pub state: TxState,
+}
+
+// DRY RUN
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+pub struct TxWeight {
+    pub ref_time: u64,
+    pub proof_size: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct DryRunOutput {
+    /// Fee the transaction would pay, without tip
+    pub partial_fee: Balance,
+    pub weight: TxWeight,
+    /// Dispatch class, `normal`, `operational` or `mandatory`
+    pub class: String,
+    /// Whether the call would be dispatched successfully
+    pub success: bool,
+    /// Dispatch or validity error the call would fail with
+    pub error: Option<ErrorDetails>,
+}
+
+// UNSIGNED PAYLOAD
+
+/// Mortal era of a transaction, valid for `period` blocks from `block_number`
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct TxEra {
+    pub period: u64,
+    pub block_number: u32,
+    pub block_hash: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct UnsignedTxOutput {
+    /// Pass back to `tx/submit_signed` along with the signature
+    pub payload_id: PayloadId,
+    /// Hex encoded bytes the signer signs
+    pub payload: String,
+    pub signer: Account,
+    pub nonce: u64,
+    pub era: TxEra,
+    pub tip: Balance,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SubmitSignedInput {
+    pub mode: Option<TxMode>,
+    pub wait_for: Option<WaitFor>,
+    pub webhook: Option<String>,
+    pub payload_id: PayloadId,
+    /// Hex encoded sr25519 signature of the payload
+    pub signature: String,
+}
+
+// TX STATUS
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct TxStatusInput {
+    pub tx_id: TxId,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct TxStatusOutput {
+    pub tx_id: TxId,
+    pub state: TxState,
+    pub block_hash: Option<String>,
+    pub events: Vec<TxEvent>,
+    pub error: Option<String>,
+}
@@ -1,9 +1,11 @@
 use crate::primitives::*;
+use crate::tx::TxParams;
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct AddValidatorInput {
-    pub seed: Seed,
+    #[serde(flatten)]
+    pub tx: TxParams,
     pub validator_id: ValidatorId,
 }
 
@@ -14,7 +16,8 @@ pub struct AddValidatorOutput {
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct RemoveValidatorInput {
-    pub seed: Seed,
+    #[serde(flatten)]
+    pub tx: TxParams,
     pub validator_id: ValidatorId,
 }
 
@@ -25,7 +28,8 @@ pub struct RemoveValidatorOutput {
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct SetKeysInput {
-    pub seed: Seed,
+    #[serde(flatten)]
+    pub tx: TxParams,
     pub aura: String,
     pub grandpa: String,
 }
@@ -39,6 +43,7 @@ pub struct SetKeysOutput {
 #[derive(Serialize, Deserialize, Debug)]
 pub struct IsValidatorInput {
     pub account: Account,
+    pub at: Option<BlockAt>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -1,13 +1,14 @@
+use crate::auth::Caller;
+use crate::error::ApiError;
 use crate::state::*;
+use crate::tx::{respond, submit, TxOptions};
 use crate::util::*;
 use actix_web::{error, web, HttpRequest, HttpResponse};
 use contract_api_types::calls::RefundInput;
 use rand::prelude::*;
-use serde_json::json;
 use subxt::ext::sp_core::sr25519::Public as SubxtPublic;
 use subxt::ext::sp_core::Pair;
 use subxt::ext::sp_runtime::traits::IdentifyAccount;
-use subxt::tx::PairSigner;
 use sugarfunge_api_types::account::*;
 use sugarfunge_api_types::primitives::*;
 use sugarfunge_api_types::sugarfunge;
@@ -40,41 +41,34 @@ pub async fn seeded(req: web::Json<SeededAccountInput>) -> error::Result<HttpRes
 /// Fund a given account with amount
 pub async fn fund(
     data: web::Data<AppState>,
+    caller: Caller,
     req: web::Json<FundAccountInput>,
 ) -> error::Result<HttpResponse> {
-    let pair = get_pair_from_seed(&req.seed).unwrap(); // Assuming get_pair_from_seed now returns the correct type
-    let signer = PairSigner::new(pair);
+    let signer = get_signer(&data, &caller, &req.tx)?;
     let account = subxt::utils::AccountId32::try_from(&req.to).map_err(map_account_err)?;
     let account = subxt::utils::MultiAddress::Id(account);
     let amount_input = req.amount;
-    let api = &data.api;
-
     let call = sugarfunge::tx()
         .balances()
         .transfer(account, amount_input.into());
 
-    let result = api
-        .tx()
-        .sign_and_submit_then_watch(&call, &signer, Default::default())
-        .await
-        .map_err(map_subxt_err)?
-        .wait_for_finalized_success()
-        .await
-        .map_err(map_sf_err)?;
-    let result = result
-        .find_first::<sugarfunge::balances::events::Transfer>()
-        .map_err(map_subxt_err)?;
-    match result {
-        Some(event) => Ok(HttpResponse::Ok().json(FundAccountOutput {
-            from: event.from.into(),
-            to: event.to.into(),
-            amount: event.amount.into(),
-        })),
-        None => Ok(HttpResponse::BadRequest().json(RequestError {
-            message: json!("Failed to find sugarfunge::balances::events::Transfer"),
-            description: "Error in account::fund".to_string(),
-        })),
-    }
+    let options = TxOptions::new(&req.tx);
+    respond(submit(&data, &call, &signer, options).await?, |receipt| {
+        let result = receipt
+            .events
+            .find_first::<sugarfunge::balances::events::Transfer>()
+            .map_err(map_subxt_err)?;
+        match result {
+            Some(event) => Ok(receipt.json(FundAccountOutput {
+                from: event.from.into(),
+                to: event.to.into(),
+                amount: event.amount.into(),
+            })),
+            None => {
+                Err(ApiError::EventNotFound("sugarfunge::balances::events::Transfer".into()).into())
+            }
+        }
+    })
 }
 
 /// Get balance for given account
@@ -83,41 +77,25 @@ pub async fn balance(
     req: web::Json<AccountBalanceInput>,
 ) -> error::Result<HttpResponse> {
     let account = subxt::utils::AccountId32::try_from(&req.account).map_err(map_account_err)?;
-    let api = &data.api;
 
     let call = sugarfunge::storage().system().account(&account);
 
-    //let result = api.storage().fetch(&call, None).await;
-    let block = api.blocks().at_latest().await.unwrap();
-    let data = block.storage().fetch(&call).await.unwrap();
+    let block = block_at(&data, &req.at).await?;
+    let data = block.storage().fetch(&call).await.map_err(map_subxt_err)?;
     match data {
-        Some(data) => Ok(HttpResponse::Ok().json(AccountBalanceOutput {
+        Some(data) => Ok(ok_at(&block).json(AccountBalanceOutput {
             balance: data.data.free.into(),
         })),
-        None => Ok(HttpResponse::BadRequest().json(RequestError {
-            message: json!("Failed to find sugarfunge::balances::events::balance"),
-            description: "Error in account::balance".to_string(),
-        })),
+        None => Err(ApiError::NotFound("Balance not found".into()).into()),
     }
 }
 
-pub async fn get_balance(seed: &Seed) -> Option<Balance> {
-    if let Ok(pair) = get_pair_from_seed(seed) {
-        let account: SubxtPublic = pair.public();
-        let account = account.into_account();
-
-        let result: Result<AccountBalanceOutput, _> = fula_sugarfunge_req(
-            "account/balance",
-            AccountBalanceInput {
-                account: Account::from(format!("{}", account)),
-            },
-        )
-        .await;
-        if let Ok(event) = result {
-            return Some(event.balance);
-        }
-    }
-    None
+pub async fn get_balance(data: &AppState, account: &subxt::utils::AccountId32) -> Option<Balance> {
+    let call = sugarfunge::storage().system().account(account);
+
+    let storage = data.api.storage().at_latest().await.ok()?;
+    let info = storage.fetch(&call).await.ok()??;
+    Some(info.data.free.into())
 }
 
 /// Check if account exists and is active
@@ -127,54 +105,54 @@ pub async fn exists(
 ) -> error::Result<HttpResponse> {
     let account = subxt::utils::AccountId32::try_from(&req.account).map_err(map_account_err)?;
     let account_out = account.clone();
-    let api = &data.api;
 
     let call = sugarfunge::storage().system().account(&account);
 
-    let storage = api.storage().at_latest().await.map_err(map_subxt_err)?;
+    let block = block_at(&data, &req.at).await?;
+    let storage = block.storage();
     let data = storage.fetch(&call).await.map_err(map_subxt_err)?;
     match data {
-        Some(data) => Ok(HttpResponse::Ok().json(AccountExistsOutput {
+        Some(data) => Ok(ok_at(&block).json(AccountExistsOutput {
             account: account_out.into(),
             exists: data.providers > 0,
         })),
-        None => Ok(HttpResponse::Ok().json(AccountExistsOutput {
+        None => Ok(ok_at(&block).json(AccountExistsOutput {
             account: account_out.into(),
             exists: false,
         })),
     }
 }
 
-pub async fn refund_fees(seed: &Seed, balance: Balance) -> error::Result<HttpResponse> {
+pub async fn refund_fees(
+    account: &subxt::utils::AccountId32,
+    balance: Balance,
+) -> error::Result<HttpResponse> {
     let result: Result<Refund, _> = fula_contract_req(
         "refund",
         RefundInput {
-            account: format!("{}", get_pair_from_seed(seed)?.public().into_account()),
+            account: format!("{}", account),
             balance: balance.into(),
         },
     )
     .await;
     match result {
         Ok(event) => Ok(HttpResponse::Ok().json(event)),
-        Err(_) => Ok(HttpResponse::BadRequest().json(RequestError {
-            message: json!("Failed to execute the refund fees"),
-            description: format!(""),
-        })),
+        Err(e) => {
+            Err(ApiError::Upstream(format!("Failed to execute the refund fees: {}", e)).into())
+        }
     }
 }
 
 /// Fund a given account with amount
 pub async fn set_balance(
     data: web::Data<AppState>,
+    caller: Caller,
     req: web::Json<SetBalanceInput>,
 ) -> error::Result<HttpResponse> {
-    let pair = get_pair_from_seed(&req.seed).unwrap(); // Assuming get_pair_from_seed now returns the correct type
-    let signer = PairSigner::new(pair);
+    let signer = get_signer(&data, &caller, &req.tx)?;
     let account = subxt::utils::AccountId32::try_from(&req.to).map_err(map_account_err)?;
     let account = subxt::utils::MultiAddress::Id(account);
     let amount_input = req.amount;
-    let api = &data.api;
-
     let call = sugarfunge::runtime_types::pallet_balances::pallet::Call::force_set_balance {
         who: account,
         new_free: amount_input.into(),
@@ -184,25 +162,24 @@ pub async fn set_balance(
 
     let sudo_call = sugarfunge::tx().sudo().sudo(call);
 
-    let result = api
-        .tx()
-        .sign_and_submit_then_watch(&sudo_call, &signer, Default::default())
-        .await
-        .map_err(map_subxt_err)?
-        .wait_for_finalized_success()
-        .await
-        .map_err(map_sf_err)?;
-    let result = result
-        .find_first::<sugarfunge::balances::events::BalanceSet>()
-        .map_err(map_subxt_err)?;
-    match result {
-        Some(event) => Ok(HttpResponse::Ok().json(SetBalanceOutput {
-            account: event.who.into(),
-            amount: event.free.into(),
-        })),
-        None => Ok(HttpResponse::BadRequest().json(RequestError {
-            message: json!("Failed to find sugarfunge::balances::events::BalanceSet"),
-            description: "Error in account::set_balance".to_string(),
-        })),
-    }
+    let options = TxOptions::new(&req.tx);
+    respond(
+        submit(&data, &sudo_call, &signer, options).await?,
+        |receipt| {
+            let result = receipt
+                .events
+                .find_first::<sugarfunge::balances::events::BalanceSet>()
+                .map_err(map_subxt_err)?;
+            match result {
+                Some(event) => Ok(receipt.json(SetBalanceOutput {
+                    account: event.who.into(),
+                    amount: event.free.into(),
+                })),
+                None => {
+                    let event = "sugarfunge::balances::events::BalanceSet";
+                    Err(ApiError::EventNotFound(event.into()).into())
+                }
+            }
+        },
+    )
 }
@@ -1,14 +1,74 @@
 use clap::Parser;
+use std::path::PathBuf;
 // use structopt::StructOpt;
 use url::Url;
 
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
 pub struct Args {
-    #[arg(short = 's', long, value_parser, default_value = "ws://127.0.0.1:9944")]
-    pub node_server: Url,
-    #[arg(short, long, value_parser, default_value = "http://127.0.0.1:4000")]
-    pub listen: Url,
+    /// Comma separated node endpoints, the next one is used when the active one goes down
+    #[arg(
+        short = 's',
+        long,
+        value_parser,
+        value_delimiter = ',',
+        default_value = "ws://127.0.0.1:9944"
+    )]
+    pub node_server: Vec<Url>,
+    /// Address to listen on [default: http://127.0.0.1:4000]
+    #[arg(short, long, value_parser)]
+    pub listen: Option<Url>,
     #[arg(short, long = "db-uri", value_parser)]
     pub db: Option<String>,
+    #[arg(short, long, value_parser, default_value = "keystore")]
+    pub keystore: PathBuf,
+    /// Reject raw seeds in request bodies, only keystore keys can sign
+    #[arg(long)]
+    pub disable_seed: bool,
+    /// Json file with api keys and jwt secret, API_KEYS and JWT_SECRET env vars are also read
+    #[arg(short, long, value_parser)]
+    pub auth_config: Option<PathBuf>,
+    /// Serve every route to anyone when no api key or jwt secret is configured
+    #[arg(long)]
+    pub insecure_no_auth: bool,
+    /// Comma separated hosts webhooks may point to, by default any host with a public address
+    #[arg(long, value_delimiter = ',')]
+    pub webhook_hosts: Vec<String>,
+    /// Most bytes of storage held by the read cache, 0 disables it
+    #[arg(long, value_parser, default_value_t = 64 * 1024 * 1024)]
+    pub cache_max_bytes: usize,
+    /// Comma separated routes whose reads go through the cache
+    #[arg(
+        long,
+        value_delimiter = ',',
+        default_value = "fula/pool,fula/manifest/available,asset/class_info,bundle/data"
+    )]
+    pub cache_routes: Vec<String>,
+    /// Json file with the listen address, TLS, body limit and CORS policy
+    #[arg(long, value_parser)]
+    pub server_config: Option<PathBuf>,
+    /// PEM certificate chain, serves https along with --tls-key
+    #[arg(long, value_parser, requires = "tls_key")]
+    pub tls_cert: Option<PathBuf>,
+    /// PEM private key of the certificate
+    #[arg(long, value_parser, requires = "tls_cert")]
+    pub tls_key: Option<PathBuf>,
+    /// Most bytes of a request body [default: 2097152]
+    #[arg(long, value_parser)]
+    pub body_limit: Option<usize>,
+    /// Comma separated CORS origins, `*` for any and a trailing `*` for a prefix
+    #[arg(long, value_delimiter = ',')]
+    pub cors_origins: Vec<String>,
+    /// Comma separated CORS methods [default: GET,POST]
+    #[arg(long, value_delimiter = ',')]
+    pub cors_methods: Vec<String>,
+    /// Comma separated CORS request headers
+    #[arg(long, value_delimiter = ',')]
+    pub cors_headers: Vec<String>,
+    /// Allow credentials in CORS requests
+    #[arg(long)]
+    pub cors_credentials: bool,
+    /// Seconds browsers may cache a preflight response [default: 3600]
+    #[arg(long, value_parser)]
+    pub cors_max_age: Option<usize>,
 }
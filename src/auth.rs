@@ -0,0 +1,411 @@
+use crate::error::ApiError;
+use actix_web::body::EitherBody;
+use actix_web::dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::http::{header, Method};
+use actix_web::{Error, FromRequest, HttpMessage, HttpRequest, ResponseError};
+use dotenv::dotenv;
+use futures::future::{ready, LocalBoxFuture, Ready};
+use jsonwebtoken::{decode, Algorithm, DecodingKey, Validation};
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::path::Path;
+use std::sync::Arc;
+pub use sugarfunge_api_types::auth::Role;
+
+const API_KEY_HEADER: &str = "X-API-Key";
+
+const ACCESS_TOKEN: &str = "access_token";
+
+/// Routes opened by browsers without custom headers, the only ones taking `?access_token=`
+const QUERY_TOKEN_ROUTES: [&str; 2] = ["ws", "events"];
+
+/// Default access log format, with the request line logged without its access token
+pub const LOG_FORMAT: &str = r#"%a "%{request_line}xi" %s %b "%{Referer}i" "%{User-Agent}i" %T"#;
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ApiKey {
+    pub key: String,
+    pub role: Role,
+    pub name: Option<String>,
+}
+
+/// Authenticated caller of a request, the api key name or jwt subject with its role
+#[derive(Clone, Debug)]
+pub struct Caller {
+    pub principal: Option<String>,
+    pub role: Role,
+}
+
+impl FromRequest for Caller {
+    type Error = Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut actix_web::dev::Payload) -> Self::Future {
+        let caller = req.extensions().get::<Caller>().cloned();
+        let unauthenticated = || ApiError::Unauthorized("Unauthenticated request".into()).into();
+        ready(caller.ok_or_else(unauthenticated))
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct Claims {
+    sub: String,
+    role: Role,
+    exp: usize,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct AuthConfig {
+    #[serde(default)]
+    pub api_keys: Vec<ApiKey>,
+    pub jwt_secret: Option<String>,
+}
+
+impl AuthConfig {
+    /// Load the auth config from a json file, API_KEYS ("key:role,...") and
+    /// JWT_SECRET environment variables are merged on top of it
+    pub fn load(path: Option<&Path>) -> std::io::Result<Self> {
+        dotenv().ok();
+        let mut config = match path {
+            Some(path) => serde_json::from_slice(&std::fs::read(path)?)
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?,
+            None => AuthConfig::default(),
+        };
+
+        if let Ok(api_keys) = env::var("API_KEYS") {
+            for entry in api_keys.split(',').filter(|entry| !entry.is_empty()) {
+                let (key, role) = entry.rsplit_once(':').unwrap_or((entry, "read_only"));
+                let role = role
+                    .parse::<Role>()
+                    .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+                config.api_keys.push(ApiKey {
+                    key: key.to_string(),
+                    role,
+                    name: None,
+                });
+            }
+        }
+        if let Ok(jwt_secret) = env::var("JWT_SECRET") {
+            config.jwt_secret = Some(jwt_secret);
+        }
+        Ok(config)
+    }
+
+    /// Authentication is only enforced once an api key or a jwt secret is configured
+    pub fn enabled(&self) -> bool {
+        !self.api_keys.is_empty() || self.jwt_secret.is_some()
+    }
+
+    fn authenticate(&self, req: &HttpRequest) -> Result<Caller, &'static str> {
+        let token = bearer_token(req).ok_or("Missing API key or bearer token")?;
+
+        // Every key is compared so the time taken doesn't tell which one is closest
+        let api_key = self.api_keys.iter().fold(None, |found, api_key| {
+            let matches = constant_time_eq(api_key.key.as_bytes(), token.as_bytes());
+            found.or(matches.then_some(api_key))
+        });
+        if let Some(api_key) = api_key {
+            return Ok(Caller {
+                principal: api_key.name.clone(),
+                role: api_key.role,
+            });
+        }
+
+        if let Some(secret) = &self.jwt_secret {
+            let claims = decode::<Claims>(
+                &token,
+                &DecodingKey::from_secret(secret.as_bytes()),
+                &Validation::new(Algorithm::HS256),
+            )
+            .map_err(|_| "Invalid or expired token")?;
+            return Ok(Caller {
+                principal: Some(claims.claims.sub),
+                role: claims.claims.role,
+            });
+        }
+
+        Err("Invalid API key")
+    }
+}
+
+fn bearer_token(req: &HttpRequest) -> Option<String> {
+    if let Some(value) = req.headers().get(header::AUTHORIZATION) {
+        let value = value.to_str().ok()?;
+        return value
+            .strip_prefix("Bearer ")
+            .map(|token| token.trim().to_string());
+    }
+    if let Some(value) = req.headers().get(API_KEY_HEADER) {
+        return value.to_str().ok().map(|token| token.trim().to_string());
+    }
+    // Browsers can't set headers on websocket handshakes and event sources
+    if !QUERY_TOKEN_ROUTES.contains(&req.path().trim_start_matches('/')) {
+        return None;
+    }
+    url::form_urlencoded::parse(req.query_string().as_bytes())
+        .find(|(name, _)| name == ACCESS_TOKEN)
+        .map(|(_, token)| token.into_owned())
+}
+
+/// Byte comparison taking the same time wherever the inputs differ
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    let mut diff = a.len() ^ b.len();
+    for i in 0..a.len().max(b.len()) {
+        let (x, y) = (
+            a.get(i).copied().unwrap_or(0),
+            b.get(i).copied().unwrap_or(0),
+        );
+        diff |= usize::from(x ^ y);
+    }
+    diff == 0
+}
+
+/// Request line of the access log, with the value of `access_token` redacted
+pub fn redacted_request_line(req: &ServiceRequest) -> String {
+    let query = url::form_urlencoded::parse(req.query_string().as_bytes()).map(|(name, value)| {
+        if name == ACCESS_TOKEN {
+            (name, "redacted".into())
+        } else {
+            (name, value)
+        }
+    });
+    let query = url::form_urlencoded::Serializer::new(String::new())
+        .extend_pairs(query)
+        .finish();
+    if query.is_empty() {
+        format!("{} {} {:?}", req.method(), req.path(), req.version())
+    } else {
+        format!(
+            "{} {}?{} {:?}",
+            req.method(),
+            req.path(),
+            query,
+            req.version()
+        )
+    }
+}
+
+/// Minimum role required to call a route, none for a route missing from the table so it is
+/// denied until given a role
+pub fn required_role(path: &str) -> Option<Role> {
+    let role = match path.trim_start_matches('/') {
+        "health"
+        | "compat"
+        | "dynamic/storage"
+        | "multisig/account"
+        | "multisig/pending"
+        | "ws"
+        | "events"
+        | "tx/status"
+        | "account/seeded"
+        | "account/exists"
+        | "account/balance"
+        | "asset/class_info"
+        | "asset/info"
+        | "asset/balance"
+        | "asset/balances"
+        | "bundle"
+        | "bundle/data"
+        | "validator/is_online"
+        | "fula/manifest"
+        | "fula/manifest/available"
+        | "fula/manifest/available_batch"
+        | "fula/manifest/available_allaccounts_batch"
+        | "fula/manifest/storer_data"
+        | "fula/pool"
+        | "fula/pool/poolrequests"
+        | "fula/pool/users"
+        | "fula/challenge"
+        | "fula/challenge/pending"
+        | "fula/claims"
+        | "fula/file/verify"
+        | "fula/online/get_heartbeats"
+        | "fula/online/get_authored_blocks"
+        | "fula/online/get_heartbeat_time" => Role::ReadOnly,
+        // dynamic/tx can encode any call, Sudo and ValidatorSet ones included
+        "dynamic/tx"
+        | "account/set_balance"
+        | "validator/add_validator"
+        | "validator/remove_validator" => Role::Sudo,
+        path if path.starts_with("keystore/") => Role::Operator,
+        "validator/activate"
+        | "validator/set_keys"
+        | "fula/manifest/alter"
+        | "fula/manifest/available/alter"
+        | "fula/manifest/storer_data/alter" => Role::Operator,
+        "batch"
+        | "proxy"
+        | "multisig/open"
+        | "multisig/approve"
+        | "tx/submit_signed"
+        | "account/create"
+        | "account/fund"
+        | "asset/create_class"
+        | "asset/create"
+        | "asset/update_metadata"
+        | "asset/mint"
+        | "asset/burn"
+        | "asset/transfer_from"
+        | "bag/register"
+        | "bag/create"
+        | "bag/sweep"
+        | "bag/deposit"
+        | "bundle/register"
+        | "bundle/mint"
+        | "bundle/burn"
+        | "market/create_market"
+        | "market/create_market_rate"
+        | "market/deposit_assets"
+        | "market/exchange_assets"
+        | "fula/manifest/update"
+        | "fula/manifest/upload"
+        | "fula/manifest/storage"
+        | "fula/manifest/remove"
+        | "fula/manifest/remove_stored_manifest"
+        | "fula/manifest/verify"
+        | "fula/manifest/batch_upload"
+        | "fula/manifest/batch_storage"
+        | "fula/manifest/batch_remove"
+        | "fula/manifest/batch_remove_stored_manifest"
+        | "fula/mint_labor_tokens"
+        | "fula/goerli/convert_tokens"
+        | "fula/mumbai/convert_tokens"
+        | "fula/pool/create"
+        | "fula/pool/leave"
+        | "fula/pool/join"
+        | "fula/pool/cancel_join"
+        | "fula/pool/vote"
+        | "fula/challenge/generate"
+        | "fula/challenge/verify"
+        | "fula/file/provide" => Role::User,
+        _ => return None,
+    };
+    Some(role)
+}
+
+fn auth_error(req: ServiceRequest, forbidden: bool, message: &str) -> ServiceResponse {
+    let e = if forbidden {
+        ApiError::Forbidden(message.to_string())
+    } else {
+        ApiError::Unauthorized(message.to_string())
+    };
+    req.into_response(e.error_response())
+}
+
+/// Middleware checking the caller role against the route it calls
+pub struct Auth {
+    config: Arc<AuthConfig>,
+}
+
+impl Auth {
+    pub fn new(config: Arc<AuthConfig>) -> Self {
+        Self { config }
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for Auth
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Transform = AuthMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(AuthMiddleware {
+            service,
+            config: self.config.clone(),
+        }))
+    }
+}
+
+pub struct AuthMiddleware<S> {
+    service: S,
+    config: Arc<AuthConfig>,
+}
+
+impl<S, B> Service<ServiceRequest> for AuthMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        if !self.config.enabled() {
+            // Every caller is trusted when authentication is turned off
+            req.extensions_mut().insert(Caller {
+                principal: None,
+                role: Role::Sudo,
+            });
+        } else if req.method() != Method::OPTIONS {
+            let required = required_role(req.path());
+            match self.config.authenticate(req.request()) {
+                Ok(caller) if required.map_or(false, |required| caller.role >= required) => {
+                    req.extensions_mut().insert(caller);
+                }
+                Ok(_) => {
+                    let message = match required {
+                        Some(_) => "Insufficient role for this route",
+                        None => "No role is allowed on this route",
+                    };
+                    let response = auth_error(req, true, message);
+                    return Box::pin(async { Ok(response.map_into_right_body()) });
+                }
+                Err(message) => {
+                    let response = auth_error(req, false, message);
+                    return Box::pin(async { Ok(response.map_into_right_body()) });
+                }
+            }
+        }
+
+        let fut = self.service.call(req);
+        Box::pin(async move { fut.await.map(ServiceResponse::map_into_left_body) })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn routes_require_their_role() {
+        assert_eq!(required_role("/health"), Some(Role::ReadOnly));
+        assert_eq!(required_role("/events"), Some(Role::ReadOnly));
+        assert_eq!(required_role("/asset/mint"), Some(Role::User));
+        assert_eq!(required_role("/proxy"), Some(Role::User));
+        assert_eq!(required_role("/keystore/unlock"), Some(Role::Operator));
+        assert_eq!(required_role("/fula/manifest/alter"), Some(Role::Operator));
+        assert_eq!(required_role("/dynamic/tx"), Some(Role::Sudo));
+        assert_eq!(required_role("/account/set_balance"), Some(Role::Sudo));
+    }
+
+    #[test]
+    fn routes_missing_from_the_table_are_denied() {
+        assert_eq!(required_role("/unknown"), None);
+        assert_eq!(required_role("/asset/mint/extra"), None);
+        assert_eq!(required_role(""), None);
+    }
+
+    #[test]
+    fn compares_keys_byte_by_byte() {
+        assert!(constant_time_eq(b"change-me", b"change-me"));
+        assert!(!constant_time_eq(b"change-me", b"change-mE"));
+        assert!(!constant_time_eq(b"change-me", b"change-me-too"));
+        assert!(!constant_time_eq(b"", b"change-me"));
+    }
+
+    #[test]
+    fn roles_grant_the_routes_below_them() {
+        assert!(Role::Sudo > Role::Operator);
+        assert!(Role::Operator > Role::User);
+        assert!(Role::User > Role::ReadOnly);
+    }
+}
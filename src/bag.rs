@@ -1,10 +1,11 @@
 use std::str::FromStr;
 
+use crate::auth::Caller;
+use crate::error::ApiError;
 use crate::state::*;
+use crate::tx::{respond, submit, TxOptions};
 use crate::util::*;
 use actix_web::{error, web, HttpResponse};
-use serde_json::json;
-use subxt::tx::PairSigner;
 use subxt::utils::AccountId32;
 use sugarfunge_api_types::bag::*;
 use sugarfunge_api_types::primitives::*;
@@ -13,39 +14,30 @@ use sugarfunge_api_types::sugarfunge::runtime_types::bounded_collections::bounde
 
 pub async fn register(
     data: web::Data<AppState>,
+    caller: Caller,
     req: web::Json<RegisterInput>,
 ) -> error::Result<HttpResponse> {
-    let pair = get_pair_from_seed(&req.seed)?;
-    let signer = PairSigner::new(pair);
+    let signer = get_signer(&data, &caller, &req.tx)?;
     let metadata: Vec<u8> = serde_json::to_vec(&req.metadata).unwrap_or_default();
     let metadata = BoundedVec(metadata);
-    let api = &data.api;
-
     let call = sugarfunge::tx()
         .bag()
         .register(req.class_id.into(), metadata);
 
-    let result = api
-        .tx()
-        .sign_and_submit_then_watch(&call, &signer, Default::default())
-        .await
-        .map_err(map_subxt_err)?
-        .wait_for_finalized_success()
-        .await
-        .map_err(map_sf_err)?;
-    let result = result
-        .find_first::<sugarfunge::bag::events::Register>()
-        .map_err(map_subxt_err)?;
-    match result {
-        Some(event) => Ok(HttpResponse::Ok().json(RegisterOutput {
-            who: event.who.into(),
-            class_id: event.class_id.into(),
-        })),
-        None => Ok(HttpResponse::BadRequest().json(RequestError {
-            message: json!("Failed to find sugarfunge::bag::events::Register"),
-            description: String::new(),
-        })),
-    }
+    let options = TxOptions::new(&req.tx);
+    respond(submit(&data, &call, &signer, options).await?, |receipt| {
+        let result = receipt
+            .events
+            .find_first::<sugarfunge::bag::events::Register>()
+            .map_err(map_subxt_err)?;
+        match result {
+            Some(event) => Ok(receipt.json(RegisterOutput {
+                who: event.who.into(),
+                class_id: event.class_id.into(),
+            })),
+            None => Err(ApiError::EventNotFound("sugarfunge::bag::events::Register".into()).into()),
+        }
+    })
 }
 
 pub fn transform_owners_input(in_owners: Vec<String>) -> Vec<AccountId32> {
@@ -68,50 +60,44 @@ pub fn transform_owners_output(in_owners: Vec<AccountId32>) -> Vec<String> {
 
 pub async fn create(
     data: web::Data<AppState>,
+    caller: Caller,
     req: web::Json<CreateInput>,
 ) -> error::Result<HttpResponse> {
-    let pair = get_pair_from_seed(&req.seed)?;
-    let signer = PairSigner::new(pair);
+    let signer = get_signer(&data, &caller, &req.tx)?;
     let owners = transform_owners_input(transform_vec_account_to_string(req.owners.clone()));
-    let api = &data.api;
-
     let call = sugarfunge::tx().bag().create(
         req.class_id.into(),
         owners,
         transform_vec_balance_to_u128(&req.shares),
     );
 
-    let result = api
-        .tx()
-        .sign_and_submit_then_watch(&call, &signer, Default::default())
-        .await
-        .map_err(map_subxt_err)?
-        .wait_for_finalized_success()
-        .await
-        .map_err(map_sf_err)?;
-    let result = result
-        .find_first::<sugarfunge::bag::events::Created>()
-        .map_err(map_subxt_err)?;
-    match result {
-        Some(event) => Ok(HttpResponse::Ok().json(CreateOutput {
-            bag: event.bag.into(),
-            class_id: event.class_id.into(),
-            asset_id: event.asset_id.into(),
-            owners: transform_vec_string_to_account(transform_owners_output(event.owners)),
-        })),
-        None => Ok(HttpResponse::BadRequest().json(RequestError {
-            message: json!("Failed to find sugarfunge::bag::events::AccountCreated"),
-            description: String::new(),
-        })),
-    }
+    let options = TxOptions::new(&req.tx);
+    respond(submit(&data, &call, &signer, options).await?, |receipt| {
+        let result = receipt
+            .events
+            .find_first::<sugarfunge::bag::events::Created>()
+            .map_err(map_subxt_err)?;
+        match result {
+            Some(event) => Ok(receipt.json(CreateOutput {
+                bag: event.bag.into(),
+                class_id: event.class_id.into(),
+                asset_id: event.asset_id.into(),
+                owners: transform_vec_string_to_account(transform_owners_output(event.owners)),
+            })),
+            None => {
+                let event = "sugarfunge::bag::events::AccountCreated";
+                Err(ApiError::EventNotFound(event.into()).into())
+            }
+        }
+    })
 }
 
 pub async fn sweep(
     data: web::Data<AppState>,
+    caller: Caller,
     req: web::Json<SweepInput>,
 ) -> error::Result<HttpResponse> {
-    let pair = get_pair_from_seed(&req.seed)?;
-    let signer = PairSigner::new(pair);
+    let signer = get_signer(&data, &caller, &req.tx)?;
     let bag_public = sp_core::sr25519::Public::from_str(&req.bag).map_err(map_account_err)?;
     let bag_array: [u8; 32] = bag_public.0;
     let bag = AccountId32::from(bag_array);
@@ -119,45 +105,34 @@ pub async fn sweep(
     let to_public = sp_core::sr25519::Public::from_str(&req.to).map_err(map_account_err)?;
     let to_array: [u8; 32] = to_public.0;
     let to = AccountId32::from(to_array);
-    let api = &data.api;
-
     let call = sugarfunge::tx().bag().sweep(to.into(), bag.into());
 
-    let result = api
-        .tx()
-        .sign_and_submit_then_watch(&call, &signer, Default::default())
-        .await
-        .map_err(map_subxt_err)?
-        .wait_for_finalized_success()
-        .await
-        .map_err(map_sf_err)?;
-    let result = result
-        .find_first::<sugarfunge::bag::events::Sweep>()
-        .map_err(map_subxt_err)?;
-    match result {
-        Some(event) => Ok(HttpResponse::Ok().json(SweepOutput {
-            bag: event.bag.into(),
-            who: event.who.into(),
-            to: event.to.into(),
-        })),
-        None => Ok(HttpResponse::BadRequest().json(RequestError {
-            message: json!("Failed to find sugarfunge::bag::events::Sweep"),
-            description: String::new(),
-        })),
-    }
+    let options = TxOptions::new(&req.tx);
+    respond(submit(&data, &call, &signer, options).await?, |receipt| {
+        let result = receipt
+            .events
+            .find_first::<sugarfunge::bag::events::Sweep>()
+            .map_err(map_subxt_err)?;
+        match result {
+            Some(event) => Ok(receipt.json(SweepOutput {
+                bag: event.bag.into(),
+                who: event.who.into(),
+                to: event.to.into(),
+            })),
+            None => Err(ApiError::EventNotFound("sugarfunge::bag::events::Sweep".into()).into()),
+        }
+    })
 }
 
 pub async fn deposit(
     data: web::Data<AppState>,
+    caller: Caller,
     req: web::Json<DepositInput>,
 ) -> error::Result<HttpResponse> {
-    let pair = get_pair_from_seed(&req.seed)?;
-    let signer = PairSigner::new(pair);
+    let signer = get_signer(&data, &caller, &req.tx)?;
     let bag_public = sp_core::sr25519::Public::from_str(&req.bag).map_err(map_account_err)?;
     let bag_array: [u8; 32] = bag_public.0;
     let bag = AccountId32::from(bag_array);
-    let api = &data.api;
-
     let call = sugarfunge::tx().bag().deposit(
         bag,
         transform_vec_classid_to_u64(req.class_ids.clone()),
@@ -165,25 +140,18 @@ pub async fn deposit(
         transform_doublevec_balance_to_u128(req.amounts.clone()),
     );
 
-    let result = api
-        .tx()
-        .sign_and_submit_then_watch(&call, &signer, Default::default())
-        .await
-        .map_err(map_subxt_err)?
-        .wait_for_finalized_success()
-        .await
-        .map_err(map_sf_err)?;
-    let result = result
-        .find_first::<sugarfunge::bag::events::Deposit>()
-        .map_err(map_subxt_err)?;
-    match result {
-        Some(event) => Ok(HttpResponse::Ok().json(DepositOutput {
-            bag: event.bag.into(),
-            who: event.who.into(),
-        })),
-        None => Ok(HttpResponse::BadRequest().json(RequestError {
-            message: json!("Failed to find sugarfunge::bag::events::Deposit"),
-            description: String::new(),
-        })),
-    }
+    let options = TxOptions::new(&req.tx);
+    respond(submit(&data, &call, &signer, options).await?, |receipt| {
+        let result = receipt
+            .events
+            .find_first::<sugarfunge::bag::events::Deposit>()
+            .map_err(map_subxt_err)?;
+        match result {
+            Some(event) => Ok(receipt.json(DepositOutput {
+                bag: event.bag.into(),
+                who: event.who.into(),
+            })),
+            None => Err(ApiError::EventNotFound("sugarfunge::bag::events::Deposit".into()).into()),
+        }
+    })
 }
@@ -0,0 +1,196 @@
+use crate::auth::Caller;
+use crate::error::ApiError;
+use crate::state::*;
+use crate::tx::{decode_event, respond, submit, TxEvents, TxOptions};
+use crate::util::*;
+use actix_web::{error, web, HttpResponse};
+use codec::Decode;
+use std::str::FromStr;
+use subxt::error::DispatchError;
+use subxt::tx::TxPayload;
+use subxt::utils::{AccountId32, H256};
+use sugarfunge_api_types::batch::*;
+use sugarfunge_api_types::primitives::*;
+use sugarfunge_api_types::sugarfunge;
+use sugarfunge_api_types::sugarfunge::runtime_types::bounded_collections::bounded_vec::BoundedVec;
+use sugarfunge_api_types::sugarfunge::runtime_types::sugarfunge_runtime::RuntimeCall;
+use sugarfunge_api_types::tx::TxEvent;
+
+/// Pallets whose events belong to the extrinsic rather than to one of the batched calls
+const EXTRINSIC_PALLETS: [&str; 2] = ["System", "TransactionPayment"];
+
+/// Submit several calls as a single `utility` extrinsic
+pub async fn batch(
+    data: web::Data<AppState>,
+    caller: Caller,
+    req: web::Json<BatchInput>,
+) -> error::Result<HttpResponse> {
+    if req.calls.is_empty() {
+        return Err(ApiError::InvalidInput("No calls to batch".into()).into());
+    }
+    let signer = get_signer(&data, &caller, &req.tx)?;
+    let calls = req
+        .calls
+        .iter()
+        .map(|call| to_runtime_call(&data, call))
+        .collect::<error::Result<Vec<_>>>()?;
+    let kind = req.kind.unwrap_or_default();
+
+    let options = TxOptions::new(&req.tx);
+    let submitted = match kind {
+        BatchKind::BatchAll => {
+            let call = sugarfunge::tx().utility().batch_all(calls);
+            submit(&data, &call, &signer, options).await?
+        }
+        BatchKind::Batch => {
+            let call = sugarfunge::tx().utility().batch(calls);
+            submit(&data, &call, &signer, options).await?
+        }
+        BatchKind::ForceBatch => {
+            let call = sugarfunge::tx().utility().force_batch(calls);
+            submit(&data, &call, &signer, options).await?
+        }
+    };
+    respond(submitted, |receipt| {
+        let items = batch_items(&data, &receipt.events, req.calls.len())?;
+        Ok(receipt.json(BatchOutput {
+            kind,
+            completed: items.iter().all(|item| item.success),
+            items,
+        }))
+    })
+}
+
+/// Runtime call of a batched route input, built like the route builds its call
+pub fn to_runtime_call(data: &AppState, call: &BatchCall) -> error::Result<RuntimeCall> {
+    match call {
+        BatchCall::AssetMint(req) => {
+            let to = AccountId32::try_from(&req.to).map_err(map_account_err)?;
+            let call = sugarfunge::tx().asset().mint(
+                to,
+                req.class_id.into(),
+                req.asset_id.into(),
+                req.amount.into(),
+            );
+            runtime_call(data, &call)
+        }
+        BatchCall::AssetBurn(req) => {
+            let from = AccountId32::try_from(&req.from).map_err(map_account_err)?;
+            let call = sugarfunge::tx().asset().burn(
+                from,
+                req.class_id.into(),
+                req.asset_id.into(),
+                req.amount.into(),
+            );
+            runtime_call(data, &call)
+        }
+        BatchCall::AssetTransferFrom(req) => {
+            let from = AccountId32::try_from(&req.from).map_err(map_account_err)?;
+            let to = AccountId32::try_from(&req.to).map_err(map_account_err)?;
+            let call = sugarfunge::tx().asset().transfer_from(
+                from,
+                to,
+                req.class_id.into(),
+                req.asset_id.into(),
+                req.amount.into(),
+            );
+            runtime_call(data, &call)
+        }
+        BatchCall::BagDeposit(req) => {
+            let bag = AccountId32::try_from(&req.bag).map_err(map_account_err)?;
+            let call = sugarfunge::tx().bag().deposit(
+                bag,
+                transform_vec_classid_to_u64(req.class_ids.clone()),
+                transform_doublevec_assetid_to_u64(req.asset_ids.clone()),
+                transform_doublevec_balance_to_u128(req.amounts.clone()),
+            );
+            runtime_call(data, &call)
+        }
+        BatchCall::BundleMint(req) => {
+            let from = AccountId32::try_from(&req.from).map_err(map_account_err)?;
+            let to = AccountId32::try_from(&req.to).map_err(map_account_err)?;
+            let bundle_id = H256::from_str(req.bundle_id.as_str()).map_err(|_| {
+                ApiError::InvalidInput(format!("Invalid bundle id {}", req.bundle_id.as_str()))
+            })?;
+            let call =
+                sugarfunge::tx()
+                    .bundle()
+                    .mint_bundle(from, to, bundle_id, req.amount.into());
+            runtime_call(data, &call)
+        }
+        BatchCall::PoolVote(req) => {
+            let account = AccountId32::try_from(&req.account).map_err(map_account_err)?;
+            let peer_id = BoundedVec(String::from(&req.peer_id).into_bytes());
+            let call =
+                sugarfunge::tx()
+                    .pool()
+                    .vote(req.pool_id.into(), account, req.vote_value, peer_id);
+            runtime_call(data, &call)
+        }
+    }
+}
+
+/// Runtime call a payload encodes to, as the `utility` calls take them
+fn runtime_call<Call: TxPayload>(data: &AppState, call: &Call) -> error::Result<RuntimeCall> {
+    let call_data = data.api.tx().call_data(call).map_err(map_subxt_err)?;
+    RuntimeCall::decode(&mut &call_data[..]).map_err(|e| ApiError::Decoding(e.to_string()).into())
+}
+
+/// Splits the events of a batch between its calls. `ItemCompleted` and `ItemFailed` close the
+/// events of a call, `BatchInterrupted` stops the batch and the calls after it never run.
+fn batch_items(
+    data: &AppState,
+    events: &TxEvents,
+    len: usize,
+) -> error::Result<Vec<BatchItemOutput>> {
+    let mut items: Vec<BatchItemOutput> = Vec::with_capacity(len);
+    let mut item_events: Vec<TxEvent> = Vec::new();
+    for event in events.iter() {
+        let event = event.map_err(map_subxt_err)?;
+        if EXTRINSIC_PALLETS.contains(&event.pallet_name()) {
+            continue;
+        }
+        if event.pallet_name() != "Utility" {
+            item_events.push(decode_event(&event));
+            continue;
+        }
+        let error = match event.variant_name() {
+            "ItemCompleted" => None,
+            "ItemFailed" => Some(dispatch_error(data, event.field_bytes())),
+            // The index of the failed call comes before the error
+            "BatchInterrupted" => {
+                let error = event.field_bytes().get(4..).ok_or_else(|| {
+                    ApiError::Decoding("BatchInterrupted event is too short".into())
+                })?;
+                Some(dispatch_error(data, error))
+            }
+            _ => continue,
+        };
+        items.push(BatchItemOutput {
+            index: items.len() as u32,
+            success: error.is_none(),
+            error,
+            events: std::mem::take(&mut item_events),
+        });
+        if event.variant_name() == "BatchInterrupted" {
+            break;
+        }
+    }
+    while items.len() < len {
+        items.push(BatchItemOutput {
+            index: items.len() as u32,
+            success: false,
+            error: Some("Not executed".into()),
+            events: vec![],
+        });
+    }
+    Ok(items)
+}
+
+/// Message of a dispatch error emitted in a `utility` event, like `Asset::InsufficientBalance`
+pub fn dispatch_error(data: &AppState, bytes: &[u8]) -> String {
+    match DispatchError::decode_from(bytes, data.api.metadata()) {
+        Ok(e) => ApiError::from(subxt::Error::Runtime(e)).to_string(),
+        Err(e) => e.to_string(),
+    }
+}
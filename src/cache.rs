@@ -0,0 +1,211 @@
+use crate::state::ClientAPI;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
+use subxt::utils::H256;
+
+/// Bookkeeping bytes counted towards the memory limit for every entry
+const ENTRY_OVERHEAD: usize = 64;
+
+/// How long to wait before subscribing again when the finalized heads subscription ends
+const RESUBSCRIBE_DELAY: Duration = Duration::from_secs(5);
+
+#[derive(Clone, Debug)]
+pub struct CacheConfig {
+    /// Most bytes of keys and values held at once, 0 disables the cache
+    pub max_bytes: usize,
+    /// Routes whose reads go through the cache, like `fula/pool`
+    pub routes: Vec<String>,
+}
+
+/// Storage key page: block, prefix, start key and count
+type PageKey = (H256, Vec<u8>, Option<Vec<u8>>, u32);
+
+#[derive(Default)]
+struct Entries {
+    values: HashMap<(H256, Vec<u8>), Option<Vec<u8>>>,
+    pages: HashMap<PageKey, Vec<Vec<u8>>>,
+    bytes: usize,
+}
+
+impl Entries {
+    fn reserve(&mut self, max_bytes: usize, bytes: usize) -> bool {
+        if self.bytes + bytes > max_bytes {
+            return false;
+        }
+        self.bytes += bytes;
+        true
+    }
+}
+
+/// Read-through cache of storage values and key pages, keyed by block hash. State at a block
+/// never changes, so entries are only dropped when a new block is finalized.
+pub struct Cache {
+    config: CacheConfig,
+    head: RwLock<Option<H256>>,
+    entries: Mutex<Entries>,
+}
+
+impl Cache {
+    pub fn new(config: CacheConfig) -> Self {
+        Cache {
+            config,
+            head: RwLock::new(None),
+            entries: Mutex::new(Entries::default()),
+        }
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.config.max_bytes > 0
+    }
+
+    /// Latest finalized block seen by the subscription
+    pub fn head(&self) -> Option<H256> {
+        *self.head.read().unwrap()
+    }
+
+    /// Moves the cache to a newly finalized block, entries of every other block are dropped
+    pub fn set_head(&self, hash: H256) {
+        *self.head.write().unwrap() = Some(hash);
+        let mut entries = self.entries.lock().unwrap();
+        entries.values.retain(|(block, _), _| *block == hash);
+        entries.pages.retain(|(block, ..), _| *block == hash);
+        let values = entries
+            .values
+            .iter()
+            .map(|((_, key), value)| entry_bytes(key, value.as_deref()));
+        let pages = entries
+            .pages
+            .iter()
+            .map(|((_, prefix, ..), keys)| page_bytes(prefix, keys));
+        let bytes = values.chain(pages).sum();
+        entries.bytes = bytes;
+    }
+
+    /// Forgets the head and every entry, until the subscription finalizes a block again
+    fn reset(&self) {
+        *self.head.write().unwrap() = None;
+        *self.entries.lock().unwrap() = Entries::default();
+    }
+
+    /// Cache scope of a single request, `None` if `path` isn't a cached route
+    pub fn scope(&self, path: &str) -> Option<CacheScope<'_>> {
+        let path = path.trim_start_matches('/');
+        if !self.enabled() || !self.config.routes.iter().any(|route| route == path) {
+            return None;
+        }
+        Some(CacheScope {
+            cache: self,
+            missed: AtomicBool::new(false),
+        })
+    }
+}
+
+fn entry_bytes(key: &[u8], value: Option<&[u8]>) -> usize {
+    ENTRY_OVERHEAD + key.len() + value.map_or(0, |value| value.len())
+}
+
+fn page_bytes(prefix: &[u8], keys: &[Vec<u8>]) -> usize {
+    ENTRY_OVERHEAD + prefix.len() + keys.iter().map(|key| key.len()).sum::<usize>()
+}
+
+/// Cache as seen by one request, remembers whether any read missed
+pub struct CacheScope<'a> {
+    cache: &'a Cache,
+    missed: AtomicBool,
+}
+
+impl CacheScope<'_> {
+    /// Value stored under `key` at `block`, the outer `None` is a miss
+    pub fn value(&self, block: H256, key: &[u8]) -> Option<Option<Vec<u8>>> {
+        let entries = self.cache.entries.lock().unwrap();
+        let value = entries.values.get(&(block, key.to_vec())).cloned();
+        if value.is_none() {
+            self.missed.store(true, Ordering::Relaxed);
+        }
+        value
+    }
+
+    pub fn insert_value(&self, block: H256, key: Vec<u8>, value: Option<Vec<u8>>) {
+        let mut entries = self.cache.entries.lock().unwrap();
+        if entries.reserve(
+            self.cache.config.max_bytes,
+            entry_bytes(&key, value.as_deref()),
+        ) {
+            entries.values.insert((block, key), value);
+        }
+    }
+
+    pub fn page(
+        &self,
+        block: H256,
+        prefix: &[u8],
+        start_key: Option<&[u8]>,
+        count: u32,
+    ) -> Option<Vec<Vec<u8>>> {
+        let entries = self.cache.entries.lock().unwrap();
+        let page_key = (
+            block,
+            prefix.to_vec(),
+            start_key.map(|key| key.to_vec()),
+            count,
+        );
+        let page = entries.pages.get(&page_key).cloned();
+        if page.is_none() {
+            self.missed.store(true, Ordering::Relaxed);
+        }
+        page
+    }
+
+    pub fn insert_page(
+        &self,
+        block: H256,
+        prefix: &[u8],
+        start_key: Option<&[u8]>,
+        count: u32,
+        keys: Vec<Vec<u8>>,
+    ) {
+        let mut entries = self.cache.entries.lock().unwrap();
+        if entries.reserve(self.cache.config.max_bytes, page_bytes(prefix, &keys)) {
+            let page_key = (
+                block,
+                prefix.to_vec(),
+                start_key.map(|key| key.to_vec()),
+                count,
+            );
+            entries.pages.insert(page_key, keys);
+        }
+    }
+
+    /// `hit` if every read of the request was served from the cache
+    pub fn status(&self) -> &'static str {
+        if self.missed.load(Ordering::Relaxed) {
+            "miss"
+        } else {
+            "hit"
+        }
+    }
+}
+
+/// Follows finalized heads to invalidate the cache, resubscribing whenever the subscription ends
+pub async fn follow_finalized(api: ClientAPI, cache: Arc<Cache>) {
+    loop {
+        match api.blocks().subscribe_finalized().await {
+            Ok(mut blocks) => {
+                while let Some(block) = blocks.next().await {
+                    match block {
+                        Ok(block) => cache.set_head(block.hash()),
+                        Err(e) => {
+                            log::warn!("Finalized heads subscription failed: {}", e);
+                            break;
+                        }
+                    }
+                }
+            }
+            Err(e) => log::warn!("Could not subscribe to finalized heads: {}", e),
+        }
+        cache.reset();
+        actix_web::rt::time::sleep(RESUBSCRIBE_DELAY).await;
+    }
+}
@@ -0,0 +1,135 @@
+use crate::error::ApiError;
+use crate::state::{AppState, ClientAPI};
+use actix_web::{error, web, HttpResponse};
+use codec::Decode;
+use std::sync::RwLock;
+use subxt::metadata::types::PalletMetadata;
+use subxt::Metadata;
+use sugarfunge_api_types::compat::*;
+use sugarfunge_api_types::SUGARFUNGE_METADATA;
+
+/// Compatibility of the compiled-in runtime types with the runtime the node runs. Routes are
+/// refused only when a pallet they use changed, the rest of the api keeps serving.
+pub struct Compat {
+    compiled: Metadata,
+    report: RwLock<CompatOutput>,
+}
+
+impl Compat {
+    pub fn new() -> Result<Self, codec::Error> {
+        Ok(Compat {
+            compiled: Metadata::decode(&mut &SUGARFUNGE_METADATA[..])?,
+            report: RwLock::new(CompatOutput {
+                compatible: true,
+                ..Default::default()
+            }),
+        })
+    }
+
+    /// Compares the call and storage hashes of every compiled pallet against the live metadata
+    pub fn check(&self, api: &ClientAPI) {
+        let live = api.metadata();
+        let incompatible: Vec<PalletCompat> = self
+            .compiled
+            .pallets()
+            .filter_map(|pallet| diff_pallet(&pallet, live.pallet_by_name(pallet.name())))
+            .collect();
+        let report = CompatOutput {
+            spec_version: api.runtime_version().spec_version,
+            compatible: incompatible.is_empty(),
+            incompatible,
+        };
+        for pallet in report.incompatible.iter() {
+            log::warn!(
+                "Pallet {} is incompatible with runtime {}, its routes are refused",
+                pallet.pallet,
+                report.spec_version
+            );
+        }
+        *self.report.write().unwrap() = report;
+    }
+
+    pub fn report(&self) -> CompatOutput {
+        self.report.read().unwrap().clone()
+    }
+
+    /// Error for a request to `path` if a pallet the route uses is incompatible
+    pub fn refusal(&self, path: &str) -> Option<ApiError> {
+        let report = self.report.read().unwrap();
+        let pallet = route_pallets(path)
+            .iter()
+            .find(|pallet| report.incompatible.iter().any(|p| p.pallet == **pallet))?;
+        Some(ApiError::IncompatibleRuntime(format!(
+            "Pallet {} changed in runtime {}, the api must be regenerated",
+            pallet, report.spec_version
+        )))
+    }
+}
+
+/// Calls and storage entries of `compiled` that differ in `live`, `None` if there are none
+fn diff_pallet(compiled: &PalletMetadata, live: Option<PalletMetadata>) -> Option<PalletCompat> {
+    let calls = compiled
+        .call_variants()
+        .unwrap_or_default()
+        .iter()
+        .map(|call| call.name.clone());
+    let storage = compiled
+        .storage()
+        .map(|storage| storage.entries())
+        .unwrap_or_default()
+        .iter()
+        .map(|entry| entry.name().to_string());
+
+    let (missing, calls, storage) = match live {
+        None => (true, calls.collect(), storage.collect()),
+        Some(live) if live.hash() == compiled.hash() => return None,
+        Some(live) => (
+            false,
+            calls
+                .filter(|call| compiled.call_hash(call) != live.call_hash(call))
+                .collect::<Vec<_>>(),
+            storage
+                .filter(|entry| compiled.storage_hash(entry) != live.storage_hash(entry))
+                .collect::<Vec<_>>(),
+        ),
+    };
+    if !missing && calls.is_empty() && storage.is_empty() {
+        return None;
+    }
+    Some(PalletCompat {
+        pallet: compiled.name().to_string(),
+        missing,
+        calls,
+        storage,
+    })
+}
+
+/// Pallets whose calls or storage a route uses
+pub fn route_pallets(path: &str) -> &'static [&'static str] {
+    match path.trim_start_matches('/') {
+        "account/exists" | "account/balance" => &["System"],
+        "account/fund" => &["Balances"],
+        "account/set_balance" => &["Sudo", "Balances"],
+        "validator/add_validator" | "validator/remove_validator" => &["Sudo", "ValidatorSet"],
+        "validator/set_keys" => &["Session"],
+        "batch" => &["Utility", "Asset", "Bag", "Bundle", "Pool"],
+        // The Multisig pallet is not in the compiled metadata, its calls are encoded dynamically
+        "multisig/open" | "multisig/approve" => &["Asset", "Bag", "Bundle", "Pool"],
+        "fula/file/verify" | "fula/goerli/convert_tokens" | "fula/mumbai/convert_tokens" => {
+            &["Bundle"]
+        }
+        path if path.starts_with("asset/") => &["Asset"],
+        path if path.starts_with("bag/") => &["Bag"],
+        path if path == "bundle" || path.starts_with("bundle/") => &["Bundle"],
+        path if path.starts_with("market/") => &["Market"],
+        path if path.starts_with("validator/") => &["ValidatorSet"],
+        path if path.starts_with("fula/pool") => &["Pool"],
+        path if path.starts_with("fula/online/") => &["ImOnline"],
+        path if path.starts_with("fula/") => &["Fula"],
+        _ => &[],
+    }
+}
+
+pub async fn status(data: web::Data<AppState>) -> error::Result<HttpResponse> {
+    Ok(HttpResponse::Ok().json(data.compat.report()))
+}
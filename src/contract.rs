@@ -1,15 +1,16 @@
-use crate::account;
 use crate::account::get_balance;
+use crate::auth::Caller;
 use crate::bundle::*;
 use crate::config;
+use crate::error::ApiError;
 use crate::state::AppState;
+use crate::tx::{submit, Submitted, TxOptions, TxSigner};
 use crate::util::*;
 use actix_web::{error, web, HttpResponse};
 use codec::Encode;
 use contract_integration::types::ReceiptOutput;
 use dotenv::dotenv;
 use hex::ToHex;
-use serde_json::json;
 use sp_runtime::traits::IdentifyAccount;
 use subxt::ext::sp_core::Pair;
 use subxt::tx::PairSigner;
@@ -17,35 +18,52 @@ use subxt::utils::AccountId32;
 use sugarfunge_api_types::contract::*;
 use sugarfunge_api_types::sugarfunge;
 use sugarfunge_api_types::sugarfunge::runtime_types::bounded_collections::bounded_vec::BoundedVec;
+use sugarfunge_api_types::tx::{TxMode, WaitFor};
 
 // Functions to call the {goerli/convert} endpoint of the fula-contract-api
 pub async fn goerli_convert_to_fula_endpoint(
     data: web::Data<AppState>,
+    caller: Caller,
     req: web::Json<ConvertFulaInput>,
 ) -> error::Result<HttpResponse> {
-    convert_to_fula_call(data, req, "goerli/mint").await
+    convert_to_fula_call(data, caller, req, "goerli/mint").await
 }
 
 // Functions to call the {mumbai/convert} endpoint of the fula-contract-api
 pub async fn mumbai_convert_to_fula_endpoint(
     data: web::Data<AppState>,
+    caller: Caller,
     req: web::Json<ConvertFulaInput>,
 ) -> error::Result<HttpResponse> {
-    convert_to_fula_call(data, req, "mumbai/mint").await
+    convert_to_fula_call(data, caller, req, "mumbai/mint").await
 }
 
 pub async fn convert_to_fula_call(
     data: web::Data<AppState>,
+    caller: Caller,
     req: web::Json<ConvertFulaInput>,
     route: &'static str,
 ) -> error::Result<HttpResponse> {
+    let blocking = req.tx.mode.unwrap_or_default() == TxMode::Blocking;
+    if !blocking || req.tx.wait_for.unwrap_or_default() != WaitFor::Finalized {
+        return Err(ApiError::InvalidInput(
+            "Conversions only support the blocking mode waiting for finalization".into(),
+        )
+        .into());
+    }
+    if req.tx.signer.is_some() {
+        return Err(ApiError::InvalidInput(
+            "Conversions are signed by the api, pass a seed or key instead of a signer".into(),
+        )
+        .into());
+    }
     dotenv().ok();
     let env = config::init();
 
-    let pair = get_pair_from_seed(&req.seed)?;
-    let signer = PairSigner::new(pair);
+    let pair = get_pair(&data, &caller, &req.tx.seed, &req.tx.key)?;
+    let signer = TxSigner::Pair(PairSigner::new(pair));
 
-    let paired = get_pair_from_seed(&req.seed)?;
+    let paired = get_pair(&data, &caller, &req.tx.seed, &req.tx.key)?;
     let subxt_public = paired.public(); // subxt::ext::sp_core::sr25519::Public
 
     // Convert subxt public key to sp_core public key by extracting the raw bytes
@@ -61,7 +79,11 @@ pub async fn convert_to_fula_call(
     let account_id_bytes: [u8; 32] = *account_id.as_ref(); // Dereference to get the array
 
     // Convert the 32-byte array account ID to AccountId32
-    let account_from = AccountId32::from(account_id_bytes); // AccountId32 can be directly created from a 32-byte array
+    // The bundle is minted for the proxied account when the signer converts on its behalf
+    let account_from = match &req.tx.on_behalf_of {
+        Some(real) => AccountId32::try_from(real).map_err(map_account_err)?,
+        None => AccountId32::from(account_id_bytes),
+    };
     let account_to = account_from.clone(); // Cloning account_from for account_to
 
     // Create the bundle schema
@@ -80,12 +102,11 @@ pub async fn convert_to_fula_call(
         ]),
     );
     let bundle_id = hash(&schema.encode());
-    let api = &data.api;
 
     // Verify if the Bundle_id exist
     // println!("2. VERIFYING IF THE BUNDLE ID EXIST");
 
-    let set_balance = get_balance(&req.seed).await;
+    let set_balance = get_balance(&data, signer.account_id()).await;
 
     if let Ok(verification) = verify_bundle_exist(&data, bundle_id.encode_hex()).await {
         // If it doesn't exist, register the bundle
@@ -99,14 +120,7 @@ pub async fn convert_to_fula_call(
                 BoundedVec(vec![]),
             );
 
-            let _result = api
-                .tx()
-                .sign_and_submit_then_watch(&call, &signer, Default::default())
-                .await
-                .map_err(map_subxt_err)?
-                .wait_for_finalized_success()
-                .await
-                .map_err(map_sf_err)?;
+            submit(&data, &call, &signer, TxOptions::default()).await?;
             // println!("4. BUNDLE CREATED");
         };
 
@@ -119,22 +133,16 @@ pub async fn convert_to_fula_call(
             req.amount.into(),
         );
 
-        let result = api
-            .tx()
-            .sign_and_submit_then_watch(&call, &signer, Default::default())
-            .await
-            .map_err(map_subxt_err)?
-            .wait_for_finalized_success()
-            .await
-            .map_err(map_sf_err)?;
-        let result = result
+        // The contract mint needs the bundle mint result, so this always waits for finalization
+        let options = TxOptions::new(&req.tx).refund_fees(signer.account_id(), set_balance);
+        let receipt = match submit(&data, &call, &signer, options).await? {
+            Submitted::Included(receipt) => receipt,
+            _ => return Err(ApiError::Internal("Bundle mint was not included".into()).into()),
+        };
+        let result = receipt
+            .events
             .find_first::<sugarfunge::bundle::events::Mint>()
             .map_err(map_subxt_err)?;
-        if let Some(balance) = set_balance {
-            if let Err(value_error) = account::refund_fees(&req.seed.clone(), balance).await {
-                return Err(value_error);
-            }
-        }
         match result {
             Some(_) => {
                 // If the bundle mint is successful, execute the contract mint
@@ -149,26 +157,21 @@ pub async fn convert_to_fula_call(
                 .await;
                 match result {
                     Ok(event) => Ok(HttpResponse::Ok().json(event)),
-                    Err(_) => Ok(HttpResponse::BadRequest().json(RequestError {
-                        message: json!("Failed to execute the contract_event::MintTo"),
-                        description: format!(""),
-                    })),
+                    Err(e) => Err(ApiError::Upstream(format!(
+                        "Failed to execute the contract_event::MintTo: {}",
+                        e
+                    ))
+                    .into()),
                 }
             }
             // If the bundle mint failed, show an error to try again
-            None => Ok(HttpResponse::BadRequest().json(RequestError {
-                message: json!("Failed to execute the Bundle Mint"),
-                description: format!(""),
-            })),
+            None => Err(ApiError::EventNotFound("sugarfunge::bundle::events::Mint".into()).into()),
         }
 
         // If exist, continue
         // println!("5. THE BUNDLE ID EXISTS");
         // Mint the Bundle with the bundle_id
     } else {
-        Ok(HttpResponse::BadRequest().json(RequestError {
-            message: json!("Failed to verify if the Bundle ID exist"),
-            description: format!(""),
-        }))
+        Err(ApiError::Transport("Failed to verify if the Bundle ID exist".into()).into())
     }
 }
@@ -0,0 +1,354 @@
+use crate::auth::Caller;
+use crate::error::ApiError;
+use crate::state::*;
+use crate::tx::{decode_events, respond, submit, TxOptions};
+use crate::util::*;
+use actix_web::{error, web, HttpResponse};
+use scale_info::form::PortableForm;
+use scale_info::{Field, PortableRegistry, TypeDef, TypeDefPrimitive};
+use serde_json::Value as Json;
+use std::str::FromStr;
+use subxt::dynamic::Value;
+use subxt::ext::scale_value::{scale::decode_as_type, Composite, ValueDef};
+use subxt::metadata::types::{StorageEntryModifier, StorageEntryType};
+use subxt::utils::AccountId32;
+use sugarfunge_api_types::dynamic::*;
+
+/// Entries returned by a partial key `storage` read without a `limit`
+const DEFAULT_STORAGE_LIMIT: u32 = 100;
+
+/// Most entries returned by a single partial key `storage` read
+const MAX_STORAGE_LIMIT: u32 = 1000;
+
+/// Submits any call of the live runtime, arguments are encoded against its metadata
+pub async fn tx(
+    data: web::Data<AppState>,
+    caller: Caller,
+    req: web::Json<DynamicTxInput>,
+) -> error::Result<HttpResponse> {
+    let signer = get_signer(&data, &caller, &req.tx)?;
+    let metadata = data.api.metadata();
+    let call = metadata
+        .pallet_by_name(&req.pallet)
+        .and_then(|pallet| pallet.call_variant_by_name(&req.call))
+        .ok_or_else(|| {
+            ApiError::NotFound(format!("Call {}::{} not found", req.pallet, req.call))
+        })?;
+    let fields =
+        to_composite(metadata.types(), &call.fields, &req.args).map_err(ApiError::InvalidInput)?;
+    let call = subxt::dynamic::tx(req.pallet.as_str(), req.call.as_str(), fields);
+    // Encode up front so arguments that don't fit the call are an input error
+    data.api
+        .tx()
+        .call_data(&call)
+        .map_err(|e| ApiError::InvalidInput(e.to_string()))?;
+
+    let options = TxOptions::new(&req.tx);
+    respond(submit(&data, &call, &signer, options).await?, |receipt| {
+        Ok(receipt.json(DynamicTxOutput {
+            events: decode_events(&receipt.events),
+        }))
+    })
+}
+
+/// Reads any storage entry of the live runtime, values are decoded against its metadata
+pub async fn storage(
+    data: web::Data<AppState>,
+    req: web::Json<DynamicStorageInput>,
+) -> error::Result<HttpResponse> {
+    let metadata = data.api.metadata();
+    let types = metadata.types();
+    let entry = metadata
+        .pallet_by_name(&req.pallet)
+        .and_then(|pallet| pallet.storage())
+        .and_then(|storage| storage.entry_by_name(&req.entry))
+        .ok_or_else(|| {
+            ApiError::NotFound(format!(
+                "Storage entry {}::{} not found",
+                req.pallet, req.entry
+            ))
+        })?;
+    let (key_types, value_type) = match entry.entry_type() {
+        StorageEntryType::Plain(value_ty) => (vec![], *value_ty),
+        StorageEntryType::Map {
+            hashers,
+            key_ty,
+            value_ty,
+        } => (map_key_types(types, hashers.len(), *key_ty), *value_ty),
+    };
+    if req.keys.len() > key_types.len() {
+        return Err(ApiError::InvalidInput(format!(
+            "Storage entry {}::{} has {} keys",
+            req.pallet,
+            req.entry,
+            key_types.len()
+        ))
+        .into());
+    }
+    let keys = req
+        .keys
+        .iter()
+        .zip(key_types.iter())
+        .enumerate()
+        .map(|(i, (key, ty))| to_value(types, *ty, key).map_err(|e| format!("key {}: {}", i, e)))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(ApiError::InvalidInput)?;
+    let address = subxt::dynamic::storage(req.pallet.as_str(), req.entry.as_str(), keys);
+    let key = data
+        .api
+        .storage()
+        .address_bytes(&address)
+        .map_err(|e| ApiError::InvalidInput(e.to_string()))?;
+    let decode = |bytes: &[u8]| -> error::Result<Json> {
+        let value = decode_as_type(&mut &bytes[..], value_type, types)
+            .map_err(|e| ApiError::Decoding(e.to_string()))?;
+        serde_json::to_value(value).map_err(|e| ApiError::Decoding(e.to_string()).into())
+    };
+
+    let block = block_at(&data, &req.at).await?;
+    if req.keys.len() == key_types.len() {
+        let value = match fetch_values(&data, &block, None, vec![key]).await?.pop() {
+            Some((_, value)) => decode(&value)?,
+            None => match entry.modifier() {
+                StorageEntryModifier::Default => decode(entry.default_bytes())?,
+                StorageEntryModifier::Optional => Json::Null,
+            },
+        };
+        return Ok(ok_at(&block).json(DynamicStorageOutput {
+            value: Some(value),
+            entries: None,
+            next_cursor: None,
+        }));
+    }
+
+    let limit = req
+        .limit
+        .unwrap_or(DEFAULT_STORAGE_LIMIT)
+        .clamp(1, MAX_STORAGE_LIMIT);
+    let (entries, next_cursor) =
+        fetch_entries_page(&data, &block, None, key, Some(limit), &req.cursor).await?;
+    let entries = entries
+        .iter()
+        .map(|(key, value)| {
+            Ok(DynamicStorageEntry {
+                key: format!("0x{}", hex::encode(key)),
+                value: decode(value)?,
+            })
+        })
+        .collect::<error::Result<Vec<_>>>()?;
+    Ok(ok_at(&block).json(DynamicStorageOutput {
+        value: None,
+        entries: Some(entries),
+        next_cursor,
+    }))
+}
+
+/// Type of every key of a storage map, a map with several hashers is keyed by a tuple
+fn map_key_types(types: &PortableRegistry, hashers: usize, key_ty: u32) -> Vec<u32> {
+    if hashers == 1 {
+        return vec![key_ty];
+    }
+    match types.resolve(key_ty).map(|ty| &ty.type_def) {
+        Some(TypeDef::Tuple(tuple)) => tuple.fields.iter().map(|field| field.id).collect(),
+        _ => vec![key_ty],
+    }
+}
+
+/// Converts json to a value of type `ty`. The conversion follows the type, so hex and ss58
+/// strings become bytes, integers may be given as strings and `{ "Variant": fields }` selects
+/// an enum variant.
+fn to_value(types: &PortableRegistry, ty: u32, json: &Json) -> Result<Value, String> {
+    let ty = types
+        .resolve(ty)
+        .ok_or_else(|| format!("unknown type {}", ty))?;
+    match &ty.type_def {
+        TypeDef::Composite(composite) => {
+            to_composite(types, &composite.fields, json).map(composite_value)
+        }
+        TypeDef::Variant(variant) => {
+            let (name, fields) = match json {
+                // Options take the value itself, or null for `None`
+                _ if ty.path.ident().as_deref() == Some("Option") => match json {
+                    Json::Null => ("None", &Json::Null),
+                    json => ("Some", json),
+                },
+                Json::String(name) => (name.as_str(), &Json::Null),
+                Json::Object(object) if object.len() == 1 => {
+                    let (name, fields) = object.iter().next().unwrap();
+                    (name.as_str(), fields)
+                }
+                _ => return Err("expected a variant name or { \"Variant\": fields }".into()),
+            };
+            let variant = variant
+                .variants
+                .iter()
+                .find(|variant| variant.name == name)
+                .ok_or_else(|| format!("unknown variant {}", name))?;
+            let fields = to_composite(types, &variant.fields, fields)?;
+            Ok(Value::variant(name, fields))
+        }
+        TypeDef::Sequence(sequence) => to_sequence(types, sequence.type_param.id, None, json),
+        TypeDef::Array(array) => {
+            to_sequence(types, array.type_param.id, Some(array.len as usize), json)
+        }
+        TypeDef::Tuple(tuple) => {
+            let values = match json {
+                Json::Null if tuple.fields.is_empty() => vec![],
+                Json::Array(items) if items.len() == tuple.fields.len() => tuple
+                    .fields
+                    .iter()
+                    .zip(items.iter())
+                    .map(|(field, item)| to_value(types, field.id, item))
+                    .collect::<Result<_, _>>()?,
+                _ => return Err(format!("expected an array of {}", tuple.fields.len())),
+            };
+            Ok(Value::unnamed_composite(values))
+        }
+        TypeDef::Primitive(primitive) => to_primitive(primitive, json),
+        TypeDef::Compact(compact) => to_value(types, compact.type_param.id, json),
+        TypeDef::BitSequence(_) => Err("bit sequences are not supported".into()),
+    }
+}
+
+/// Converts json to the fields of a struct or variant, an object keyed by field name or an
+/// array in field order. A single field may also be given directly.
+fn to_composite(
+    types: &PortableRegistry,
+    fields: &[Field<PortableForm>],
+    json: &Json,
+) -> Result<Composite<()>, String> {
+    let named = !fields.is_empty() && fields.iter().all(|field| field.name.is_some());
+    let single = fields.len() == 1;
+    let items: Vec<&Json> = match json {
+        json if fields.is_empty() && is_empty(json) => vec![],
+        Json::Object(object)
+            if named && (!single || object.contains_key(field_name(&fields[0]))) =>
+        {
+            if let Some(unknown) = object
+                .keys()
+                .find(|key| !fields.iter().any(|field| field_name(field) == key.as_str()))
+            {
+                return Err(format!("unknown field {}", unknown));
+            }
+            fields
+                .iter()
+                .map(|field| {
+                    object
+                        .get(field_name(field))
+                        .ok_or_else(|| format!("missing field {}", field_name(field)))
+                })
+                .collect::<Result<_, _>>()?
+        }
+        json if single => vec![json],
+        Json::Array(items) if items.len() == fields.len() => items.iter().collect(),
+        _ => return Err(format!("expected {} fields", fields.len())),
+    };
+    let values = fields.iter().zip(items).map(|(field, item)| {
+        to_value(types, field.ty.id, item).map_err(|e| match &field.name {
+            Some(name) => format!("{}: {}", name, e),
+            None => e,
+        })
+    });
+    if named {
+        let values = fields
+            .iter()
+            .map(|field| field_name(field).to_string())
+            .zip(values)
+            .map(|(name, value)| value.map(|value| (name, value)))
+            .collect::<Result<_, _>>()?;
+        Ok(Composite::Named(values))
+    } else {
+        Ok(Composite::Unnamed(values.collect::<Result<_, _>>()?))
+    }
+}
+
+fn is_empty(json: &Json) -> bool {
+    match json {
+        Json::Null => true,
+        Json::Array(items) => items.is_empty(),
+        Json::Object(object) => object.is_empty(),
+        _ => false,
+    }
+}
+
+fn field_name(field: &Field<PortableForm>) -> &str {
+    field.name.as_deref().unwrap_or_default()
+}
+
+fn composite_value(composite: Composite<()>) -> Value {
+    Value {
+        value: ValueDef::Composite(composite),
+        context: (),
+    }
+}
+
+/// Converts json to a sequence, or an array of `len`. Byte sequences also take a `0x` hex
+/// string or a plain string, 32 byte arrays an ss58 address.
+fn to_sequence(
+    types: &PortableRegistry,
+    item_ty: u32,
+    len: Option<usize>,
+    json: &Json,
+) -> Result<Value, String> {
+    let is_u8 = matches!(
+        types.resolve(item_ty).map(|ty| &ty.type_def),
+        Some(TypeDef::Primitive(TypeDefPrimitive::U8))
+    );
+    match json {
+        Json::String(s) if is_u8 => {
+            let bytes = match s.strip_prefix("0x") {
+                Some(hex) => hex::decode(hex).map_err(|e| format!("invalid hex: {}", e))?,
+                None if len == Some(32) => AccountId32::from_str(s)
+                    .map_err(|_| format!("invalid address {}", s))?
+                    .0
+                    .to_vec(),
+                None => s.as_bytes().to_vec(),
+            };
+            Ok(Value::from_bytes(bytes))
+        }
+        Json::Array(items) => Ok(Value::unnamed_composite(
+            items
+                .iter()
+                .map(|item| to_value(types, item_ty, item))
+                .collect::<Result<Vec<_>, _>>()?,
+        )),
+        _ => Err("expected an array".into()),
+    }
+}
+
+fn to_primitive(primitive: &TypeDefPrimitive, json: &Json) -> Result<Value, String> {
+    match (primitive, json) {
+        (TypeDefPrimitive::Bool, Json::Bool(b)) => Ok(Value::bool(*b)),
+        (TypeDefPrimitive::Str, Json::String(s)) => Ok(Value::string(s.clone())),
+        (TypeDefPrimitive::Char, Json::String(s)) if s.chars().count() == 1 => {
+            Ok(Value::char(s.chars().next().unwrap()))
+        }
+        (
+            TypeDefPrimitive::U8
+            | TypeDefPrimitive::U16
+            | TypeDefPrimitive::U32
+            | TypeDefPrimitive::U64
+            | TypeDefPrimitive::U128,
+            json,
+        ) => match json {
+            Json::Number(n) => n.as_u64().map(|n| Value::u128(n as u128)),
+            Json::String(s) => s.parse::<u128>().ok().map(Value::u128),
+            _ => None,
+        }
+        .ok_or_else(|| "expected an unsigned integer".into()),
+        (
+            TypeDefPrimitive::I8
+            | TypeDefPrimitive::I16
+            | TypeDefPrimitive::I32
+            | TypeDefPrimitive::I64
+            | TypeDefPrimitive::I128,
+            json,
+        ) => match json {
+            Json::Number(n) => n.as_i64().map(|n| Value::i128(n as i128)),
+            Json::String(s) => s.parse::<i128>().ok().map(Value::i128),
+            _ => None,
+        }
+        .ok_or_else(|| "expected an integer".into()),
+        (primitive, _) => Err(format!("expected a {:?}", primitive)),
+    }
+}
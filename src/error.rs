@@ -0,0 +1,192 @@
+use crate::keystore::KeystoreError;
+use actix_web::http::StatusCode;
+use actix_web::{HttpResponse, ResponseError};
+use derive_more::Display;
+use subxt::error::DispatchError;
+use sugarfunge_api_types::error::*;
+
+/// Every error the API responds with, serialized as an `ErrorOutput`
+#[derive(Debug, Display)]
+pub enum ApiError {
+    #[display(fmt = "{}", _0)]
+    InvalidInput(String),
+    #[display(fmt = "Invalid account: {}", _0)]
+    InvalidAccount(String),
+    #[display(fmt = "Invalid seed: {}", _0)]
+    InvalidSeed(String),
+    #[display(fmt = "{}", _0)]
+    Unauthorized(String),
+    #[display(fmt = "{}", _0)]
+    Forbidden(String),
+    #[display(fmt = "{}", _0)]
+    NotFound(String),
+    #[display(fmt = "{}", _0)]
+    Keystore(KeystoreError),
+    /// A pallet error raised while dispatching the call
+    #[display(fmt = "{}::{}", pallet, variant)]
+    Pallet {
+        pallet: String,
+        variant: String,
+        docs: String,
+    },
+    /// Any other dispatch error (bad origin, arithmetic, token...)
+    #[display(fmt = "{}", _0)]
+    Dispatch(String),
+    /// The transaction was rejected by the pool, dropped or invalid
+    #[display(fmt = "{}", _0)]
+    Transaction(String),
+    /// The call succeeded but the event the handler reads was not emitted
+    #[display(fmt = "Failed to find {}", _0)]
+    EventNotFound(String),
+    #[display(fmt = "{}", _0)]
+    Transport(String),
+    #[display(fmt = "{}", _0)]
+    Decoding(String),
+    /// The fula-contract-api or a webhook failed
+    #[display(fmt = "{}", _0)]
+    Upstream(String),
+    /// A pallet the route uses changed in the live runtime
+    #[display(fmt = "{}", _0)]
+    IncompatibleRuntime(String),
+    #[display(fmt = "{}", _0)]
+    Internal(String),
+}
+
+impl ApiError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            ApiError::InvalidInput(_) => "invalid_input",
+            ApiError::InvalidAccount(_) => "invalid_account",
+            ApiError::InvalidSeed(_) => "invalid_seed",
+            ApiError::Unauthorized(_) => "unauthorized",
+            ApiError::Forbidden(_) => "forbidden",
+            ApiError::NotFound(_) => "not_found",
+            ApiError::Keystore(e) => match e {
+                KeystoreError::NotFound(_) => "key_not_found",
+                KeystoreError::AliasTaken(_) => "key_alias_taken",
+                KeystoreError::AlreadyExists(_) => "key_already_exists",
+                KeystoreError::Locked(_) => "key_locked",
+                KeystoreError::NotAllowed(_) => "key_not_allowed",
+                KeystoreError::InvalidPassword => "key_invalid_password",
+                KeystoreError::InvalidSeed => "invalid_seed",
+                KeystoreError::Crypto(_) | KeystoreError::Io(_) | KeystoreError::File(_) => {
+                    "keystore_error"
+                }
+            },
+            ApiError::Pallet { .. } => "pallet_error",
+            ApiError::Dispatch(_) => "dispatch_error",
+            ApiError::Transaction(_) => "transaction_error",
+            ApiError::EventNotFound(_) => "event_not_found",
+            ApiError::Transport(_) => "transport_error",
+            ApiError::Decoding(_) => "decoding_error",
+            ApiError::Upstream(_) => "upstream_error",
+            ApiError::IncompatibleRuntime(_) => "incompatible_runtime",
+            ApiError::Internal(_) => "internal_error",
+        }
+    }
+
+    pub fn category(&self) -> ErrorCategory {
+        match self {
+            ApiError::InvalidInput(_) | ApiError::InvalidAccount(_) | ApiError::InvalidSeed(_) => {
+                ErrorCategory::Input
+            }
+            ApiError::Unauthorized(_) | ApiError::Forbidden(_) => ErrorCategory::Auth,
+            ApiError::Keystore(KeystoreError::NotAllowed(_)) => ErrorCategory::Auth,
+            ApiError::NotFound(_) => ErrorCategory::NotFound,
+            ApiError::Keystore(KeystoreError::NotFound(_)) => ErrorCategory::NotFound,
+            ApiError::Keystore(KeystoreError::Crypto(_))
+            | ApiError::Keystore(KeystoreError::Io(_))
+            | ApiError::Keystore(KeystoreError::File(_)) => ErrorCategory::Internal,
+            ApiError::Keystore(_) => ErrorCategory::Input,
+            ApiError::Pallet { .. } | ApiError::Dispatch(_) => ErrorCategory::Dispatch,
+            ApiError::Transaction(_) => ErrorCategory::Transaction,
+            ApiError::EventNotFound(_) | ApiError::Decoding(_) => ErrorCategory::Decoding,
+            ApiError::Transport(_) => ErrorCategory::Transport,
+            ApiError::Upstream(_) => ErrorCategory::Upstream,
+            ApiError::IncompatibleRuntime(_) => ErrorCategory::Unavailable,
+            ApiError::Internal(_) => ErrorCategory::Internal,
+        }
+    }
+
+    pub fn details(&self) -> ErrorDetails {
+        let (pallet, variant, docs) = match self {
+            ApiError::Pallet {
+                pallet,
+                variant,
+                docs,
+            } => (
+                Some(pallet.clone()),
+                Some(variant.clone()),
+                Some(docs.clone()),
+            ),
+            _ => (None, None, None),
+        };
+        ErrorDetails {
+            code: self.code().to_string(),
+            category: self.category(),
+            message: self.to_string(),
+            pallet,
+            variant,
+            docs,
+        }
+    }
+}
+
+impl ResponseError for ApiError {
+    fn status_code(&self) -> StatusCode {
+        match self.category() {
+            ErrorCategory::Input | ErrorCategory::Dispatch | ErrorCategory::Transaction => {
+                StatusCode::BAD_REQUEST
+            }
+            ErrorCategory::Auth => match self {
+                ApiError::Forbidden(_) | ApiError::Keystore(KeystoreError::NotAllowed(_)) => {
+                    StatusCode::FORBIDDEN
+                }
+                _ => StatusCode::UNAUTHORIZED,
+            },
+            ErrorCategory::NotFound => StatusCode::NOT_FOUND,
+            ErrorCategory::Transport | ErrorCategory::Upstream => StatusCode::BAD_GATEWAY,
+            ErrorCategory::Unavailable => StatusCode::SERVICE_UNAVAILABLE,
+            ErrorCategory::Decoding | ErrorCategory::Internal => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        HttpResponse::build(self.status_code()).json(ErrorOutput {
+            error: self.details(),
+        })
+    }
+}
+
+impl From<subxt::Error> for ApiError {
+    fn from(e: subxt::Error) -> Self {
+        match e {
+            subxt::Error::Runtime(DispatchError::Module(module_err)) => {
+                match module_err.details() {
+                    Ok(details) => ApiError::Pallet {
+                        pallet: details.pallet.name().to_string(),
+                        variant: details.variant.name.clone(),
+                        docs: details.variant.docs.join(" "),
+                    },
+                    Err(e) => ApiError::Decoding(e.to_string()),
+                }
+            }
+            subxt::Error::Runtime(e) => ApiError::Dispatch(e.to_string()),
+            subxt::Error::Transaction(e) => ApiError::Transaction(e.to_string()),
+            subxt::Error::Rpc(e) => ApiError::Transport(e.to_string()),
+            subxt::Error::Io(e) => ApiError::Transport(e.to_string()),
+            e @ subxt::Error::Codec(_)
+            | e @ subxt::Error::Metadata(_)
+            | e @ subxt::Error::MetadataDecoding(_)
+            | e @ subxt::Error::Decode(_)
+            | e @ subxt::Error::Encode(_) => ApiError::Decoding(e.to_string()),
+            e => ApiError::Internal(e.to_string()),
+        }
+    }
+}
+
+impl From<KeystoreError> for ApiError {
+    fn from(e: KeystoreError) -> Self {
+        ApiError::Keystore(e)
+    }
+}
@@ -0,0 +1,361 @@
+use crate::state::{AppState, ClientAPI, RpcAPI};
+use crate::util::BlockAPI;
+use actix::prelude::*;
+use futures::channel::mpsc;
+use std::ops::RangeInclusive;
+use std::sync::Arc;
+use std::time::Duration;
+use subxt::ext::scale_value::{Composite, Primitive, Value, ValueDef};
+use subxt::utils::{AccountId32, H256};
+use sugarfunge_api_types::subscription::{EventFilter, EventTopic};
+use sugarfunge_api_types::tx::TxEvent;
+
+/// Delay before following finalized blocks again after the subscription ended
+const RESUBSCRIBE_DELAY: Duration = Duration::from_secs(5);
+
+/// Blocks of events queued for a session, a session further behind is disconnected
+pub const SESSION_QUEUE: usize = 64;
+
+/// Most finalized blocks a subscription can replay
+pub const MAX_REPLAY_BLOCKS: u32 = 10_000;
+
+/// Events of a finalized block, decoded once and shared by every session
+pub struct BlockEvents {
+    pub number: u32,
+    pub hash: H256,
+    pub events: Vec<BlockEvent>,
+}
+
+pub struct BlockEvent {
+    /// Index of the event in the block
+    pub index: u32,
+    /// Field values the subscription filters look into
+    pub fields: Composite<u32>,
+    pub event: TxEvent,
+}
+
+/// Follows finalized blocks once for the whole server and broadcasts their events to the
+/// connected sessions, each through its own bounded queue
+pub struct EventBus {
+    api: ClientAPI,
+    rpc: RpcAPI,
+    sessions: Vec<mpsc::Sender<Arc<BlockEvents>>>,
+}
+
+impl EventBus {
+    pub fn new(api: ClientAPI, rpc: RpcAPI) -> Self {
+        Self {
+            api,
+            rpc,
+            sessions: Vec::new(),
+        }
+    }
+}
+
+impl Actor for EventBus {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        let (api, rpc) = (self.api.clone(), self.rpc.clone());
+        actix_web::rt::spawn(follow_finalized(api, rpc, ctx.address()));
+    }
+}
+
+/// Registers the queue of a session, the session ends its stream to leave
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct Connect(pub mpsc::Sender<Arc<BlockEvents>>);
+
+impl Handler<Connect> for EventBus {
+    type Result = ();
+
+    fn handle(&mut self, msg: Connect, _ctx: &mut Self::Context) {
+        self.sessions.push(msg.0);
+    }
+}
+
+#[derive(Message)]
+#[rtype(result = "()")]
+struct Publish(Arc<BlockEvents>);
+
+impl Handler<Publish> for EventBus {
+    type Result = ();
+
+    fn handle(&mut self, msg: Publish, _ctx: &mut Self::Context) {
+        // A full queue drops the session rather than its events, the session sees its stream end
+        self.sessions
+            .retain_mut(|session| match session.try_send(msg.0.clone()) {
+                Ok(()) => true,
+                Err(e) if e.is_full() => {
+                    log::warn!(
+                        "Dropping a subscription session {} blocks behind",
+                        SESSION_QUEUE
+                    );
+                    false
+                }
+                Err(_) => false,
+            });
+    }
+}
+
+/// Decodes the events of every finalized block and publishes them. The next block waits for the
+/// bus to take the previous one.
+async fn follow_finalized(api: ClientAPI, rpc: RpcAPI, bus: Addr<EventBus>) {
+    // Next block to publish, kept across subscriptions so none is skipped while resubscribing
+    let mut next = None;
+    loop {
+        match api.blocks().subscribe_finalized().await {
+            Ok(mut blocks) => {
+                while let Some(block) = blocks.next().await {
+                    let published = match block {
+                        Ok(block) => publish_up_to(&api, &rpc, &bus, &mut next, block).await,
+                        Err(e) => Err(e.to_string()),
+                    };
+                    match published {
+                        Ok(true) => {}
+                        Ok(false) => return,
+                        Err(e) => {
+                            log::warn!("Finalized events subscription failed: {}", e);
+                            break;
+                        }
+                    }
+                }
+            }
+            Err(e) => log::warn!("Could not subscribe to finalized events: {}", e),
+        }
+        actix_web::rt::time::sleep(RESUBSCRIBE_DELAY).await;
+    }
+}
+
+/// Publishes the blocks from `next` up to a newly finalized block, the ones finalized while no
+/// subscription was open are read back first. False once the bus is gone.
+async fn publish_up_to(
+    api: &ClientAPI,
+    rpc: &RpcAPI,
+    bus: &Addr<EventBus>,
+    next: &mut Option<u32>,
+    block: BlockAPI,
+) -> Result<bool, String> {
+    let number = block.number();
+    let from = next.unwrap_or(number);
+    for missed in from..number {
+        let events = events_at(api, rpc, missed).await?;
+        if bus.send(Publish(Arc::new(events))).await.is_err() {
+            return Ok(false);
+        }
+        *next = Some(missed + 1);
+    }
+    if number < from {
+        return Ok(true);
+    }
+    let events = decode_block(&block).await.map_err(|e| e.to_string())?;
+    if bus.send(Publish(Arc::new(events))).await.is_err() {
+        return Ok(false);
+    }
+    *next = Some(number + 1);
+    Ok(true)
+}
+
+/// Blocks a replay from `from` goes through, up to the finalized head
+pub async fn replay_range(data: &AppState, from: u32) -> Result<RangeInclusive<u32>, String> {
+    let hash = data
+        .rpc
+        .chain_get_finalized_head()
+        .await
+        .map_err(|e| e.to_string())?;
+    let head = data
+        .api
+        .blocks()
+        .at(hash)
+        .await
+        .map_err(|e| e.to_string())?
+        .number();
+    if from > head {
+        return Err(format!(
+            "Block {} is not finalized, the finalized head is {}",
+            from, head
+        ));
+    }
+    if head - from >= MAX_REPLAY_BLOCKS {
+        return Err(format!(
+            "Replays are limited to the last {} blocks, the finalized head is {}",
+            MAX_REPLAY_BLOCKS, head
+        ));
+    }
+    Ok(from..=head)
+}
+
+/// Events of a finalized block, read back from the node
+pub async fn historical_block(data: &AppState, number: u32) -> Result<BlockEvents, String> {
+    events_at(&data.api, &data.rpc, number).await
+}
+
+async fn events_at(api: &ClientAPI, rpc: &RpcAPI, number: u32) -> Result<BlockEvents, String> {
+    let hash = rpc
+        .chain_get_block_hash(Some(number.into()))
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Block {} not found", number))?;
+    let block = api.blocks().at(hash).await.map_err(|e| e.to_string())?;
+    decode_block(&block).await.map_err(|e| e.to_string())
+}
+
+async fn decode_block(block: &BlockAPI) -> Result<BlockEvents, subxt::Error> {
+    let events = block.events().await?;
+    let mut decoded = Vec::new();
+    for event in events.iter() {
+        let event = event?;
+        let fields = event.field_values().unwrap_or(Composite::Unnamed(vec![]));
+        decoded.push(BlockEvent {
+            index: event.index(),
+            event: TxEvent {
+                pallet: event.pallet_name().to_string(),
+                variant: event.variant_name().to_string(),
+                fields: serde_json::to_value(&fields).unwrap_or_default(),
+            },
+            fields,
+        });
+    }
+    Ok(BlockEvents {
+        number: block.number(),
+        hash: block.hash(),
+        events: decoded,
+    })
+}
+
+/// Topics and filter of a subscription, with the filter values parsed
+pub struct Filter {
+    topics: Vec<EventTopic>,
+    account: Option<AccountId32>,
+    class_id: Option<u64>,
+    asset_id: Option<u64>,
+    pool_id: Option<u32>,
+    cid: Option<Vec<u8>>,
+}
+
+impl Filter {
+    pub fn new(topics: &[EventTopic], filter: &EventFilter) -> Result<Self, String> {
+        if topics.is_empty() {
+            return Err("Subscribe to at least one topic".into());
+        }
+        let account = match &filter.account {
+            Some(account) => Some(
+                AccountId32::try_from(account)
+                    .map_err(|_| format!("Invalid account {}", account.as_str()))?,
+            ),
+            None => None,
+        };
+        Ok(Self {
+            topics: topics.to_vec(),
+            account,
+            class_id: filter.class_id.map(u64::from),
+            asset_id: filter.asset_id.map(u64::from),
+            pool_id: filter.pool_id.map(u32::from),
+            cid: filter
+                .cid
+                .as_ref()
+                .map(|cid| String::from(cid).into_bytes()),
+        })
+    }
+
+    pub fn matches(&self, event: &BlockEvent) -> bool {
+        let topic = self.topics.iter().any(|topic| {
+            topic.pallet == event.event.pallet
+                && (topic.events.is_empty()
+                    || topic.events.iter().any(|name| *name == event.event.variant))
+        });
+        if !topic {
+            return false;
+        }
+        if self.account.is_none()
+            && self.class_id.is_none()
+            && self.asset_id.is_none()
+            && self.pool_id.is_none()
+            && self.cid.is_none()
+        {
+            return true;
+        }
+        let fields = &event.fields;
+        let has_id = |field: &str, id: Option<u128>| match id {
+            Some(id) => any_field(fields, &|name, value| {
+                is_field(name, field) && numbers(value).contains(&id)
+            }),
+            None => true,
+        };
+        let has_account = match &self.account {
+            Some(account) => any_field(fields, &|_, value| {
+                bytes(value).as_deref() == Some(&account.0[..])
+            }),
+            None => true,
+        };
+        let has_cid = match &self.cid {
+            Some(cid) => any_field(fields, &|name, value| {
+                is_field(name, "cid") && contains_bytes(value, cid)
+            }),
+            None => true,
+        };
+        has_account
+            && has_cid
+            && has_id("class_id", self.class_id.map(u128::from))
+            && has_id("asset_id", self.asset_id.map(u128::from))
+            && has_id("pool_id", self.pool_id.map(u128::from))
+    }
+}
+
+/// Whether `matches` holds for a field nested anywhere in `composite`, given with its name
+fn any_field(
+    composite: &Composite<u32>,
+    matches: &dyn Fn(Option<&str>, &Value<u32>) -> bool,
+) -> bool {
+    let fields: Vec<(Option<&str>, &Value<u32>)> = match composite {
+        Composite::Named(fields) => fields
+            .iter()
+            .map(|(name, value)| (Some(name.as_str()), value))
+            .collect(),
+        Composite::Unnamed(values) => values.iter().map(|value| (None, value)).collect(),
+    };
+    fields.into_iter().any(|(name, value)| {
+        matches(name, value)
+            || match &value.value {
+                ValueDef::Composite(composite) => any_field(composite, matches),
+                ValueDef::Variant(variant) => any_field(&variant.values, matches),
+                _ => false,
+            }
+    })
+}
+
+/// A field named `field`, or its plural for the events carrying several values
+fn is_field(name: Option<&str>, field: &str) -> bool {
+    match name {
+        Some(name) => name == field || name.strip_suffix('s') == Some(field),
+        None => false,
+    }
+}
+
+/// Every unsigned integer nested in a value
+fn numbers(value: &Value<u32>) -> Vec<u128> {
+    match &value.value {
+        ValueDef::Primitive(Primitive::U128(n)) => vec![*n],
+        ValueDef::Composite(composite) => composite.values().flat_map(numbers).collect(),
+        ValueDef::Variant(variant) => variant.values.values().flat_map(numbers).collect(),
+        _ => vec![],
+    }
+}
+
+/// A value made of bytes, like an account id or a bounded vec
+fn bytes(value: &Value<u32>) -> Option<Vec<u8>> {
+    numbers(value)
+        .into_iter()
+        .map(|n| u8::try_from(n).ok())
+        .collect()
+}
+
+fn contains_bytes(value: &Value<u32>, target: &[u8]) -> bool {
+    bytes(value).as_deref() == Some(target)
+        || match &value.value {
+            ValueDef::Composite(composite) => composite
+                .values()
+                .any(|value| contains_bytes(value, target)),
+            _ => false,
+        }
+}
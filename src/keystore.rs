@@ -0,0 +1,540 @@
+use crate::auth::{Caller, Role};
+use crate::state::*;
+use crate::util::*;
+use actix_web::{error, web, HttpResponse};
+use argon2::Argon2;
+use chacha20poly1305::aead::{Aead, KeyInit};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use derive_more::Display;
+use rand::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+use subxt::ext::sp_core::sr25519::Pair as Sr25519Pair;
+use subxt::ext::sp_core::Pair;
+use subxt::ext::sp_runtime::traits::IdentifyAccount;
+use sugarfunge_api_types::keystore::*;
+use sugarfunge_api_types::primitives::*;
+
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+
+#[derive(Debug, Display)]
+pub enum KeystoreError {
+    #[display(fmt = "Key not found: {}", _0)]
+    NotFound(String),
+    #[display(fmt = "Alias already in use: {}", _0)]
+    AliasTaken(String),
+    #[display(fmt = "Key already in keystore: {}", _0)]
+    AlreadyExists(String),
+    #[display(fmt = "Key is locked: {}", _0)]
+    Locked(String),
+    #[display(fmt = "Key can't be used by this caller: {}", _0)]
+    NotAllowed(String),
+    #[display(fmt = "Invalid password")]
+    InvalidPassword,
+    #[display(fmt = "Invalid seed")]
+    InvalidSeed,
+    #[display(fmt = "Keystore crypto error: {}", _0)]
+    Crypto(String),
+    #[display(fmt = "Keystore io error: {}", _0)]
+    Io(std::io::Error),
+    #[display(fmt = "Keystore file error: {}", _0)]
+    File(serde_json::Error),
+}
+
+impl From<std::io::Error> for KeystoreError {
+    fn from(e: std::io::Error) -> Self {
+        KeystoreError::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for KeystoreError {
+    fn from(e: serde_json::Error) -> Self {
+        KeystoreError::File(e)
+    }
+}
+
+/// On-disk representation of a key, the secret uri is encrypted with a
+/// password derived key (argon2 + chacha20poly1305)
+#[derive(Serialize, Deserialize, Clone, Debug)]
+struct KeyFile {
+    key_id: String,
+    alias: String,
+    account: String,
+    salt: String,
+    nonce: String,
+    ciphertext: String,
+    /// Callers allowed to sign with the key, files written before keys were bound to callers
+    /// only serve sudo callers
+    #[serde(default)]
+    principals: Vec<String>,
+    #[serde(default = "sudo")]
+    min_role: Role,
+}
+
+fn sudo() -> Role {
+    Role::Sudo
+}
+
+impl KeyFile {
+    fn allows(&self, caller: &Caller) -> bool {
+        caller.role >= self.min_role
+            || caller
+                .principal
+                .as_ref()
+                .map_or(false, |principal| self.principals.contains(principal))
+    }
+}
+
+/// Encrypted sr25519 keystore, keys must be unlocked before they can sign
+pub struct Keystore {
+    path: PathBuf,
+    keys: RwLock<HashMap<String, KeyFile>>,
+    unlocked: RwLock<HashMap<String, Sr25519Pair>>,
+}
+
+impl Keystore {
+    /// Open (or create) the keystore directory and load every key file in it
+    pub fn open(path: &Path) -> Result<Self, KeystoreError> {
+        fs::create_dir_all(path)?;
+        let mut keys = HashMap::new();
+        for entry in fs::read_dir(path)? {
+            let file_path = entry?.path();
+            if file_path.extension().and_then(|ext| ext.to_str()) != Some("json") {
+                continue;
+            }
+            let key_file: KeyFile = serde_json::from_slice(&fs::read(&file_path)?)?;
+            keys.insert(key_file.key_id.clone(), key_file);
+        }
+        log::info!(
+            "Keystore loaded {} keys from {}",
+            keys.len(),
+            path.display()
+        );
+        Ok(Self {
+            path: path.to_path_buf(),
+            keys: RwLock::new(keys),
+            unlocked: RwLock::new(HashMap::new()),
+        })
+    }
+
+    /// Generate a new key and store it encrypted with password, only the principals and the
+    /// callers with `min_role` or above can sign with it
+    pub fn create(
+        &self,
+        alias: &str,
+        password: &str,
+        principals: Vec<String>,
+        min_role: Role,
+    ) -> Result<KeyInfo, KeystoreError> {
+        let (_, phrase, _) = Sr25519Pair::generate_with_phrase(None);
+        self.insert(alias, &phrase, password, principals, min_role)
+    }
+
+    /// Store an existing seed encrypted with password, bound like a created key
+    pub fn import(
+        &self,
+        alias: &str,
+        seed: &Seed,
+        password: &str,
+        principals: Vec<String>,
+        min_role: Role,
+    ) -> Result<KeyInfo, KeystoreError> {
+        self.insert(alias, seed.as_str(), password, principals, min_role)
+    }
+
+    pub fn list(&self) -> Vec<KeyInfo> {
+        let keys = self.keys.read().unwrap();
+        let unlocked = self.unlocked.read().unwrap();
+        let mut result: Vec<KeyInfo> = keys
+            .values()
+            .map(|key_file| key_info(key_file, unlocked.contains_key(&key_file.key_id)))
+            .collect();
+        result.sort_by(|a, b| a.alias.cmp(&b.alias));
+        result
+    }
+
+    /// Decrypt the key and keep it in memory until it is locked again
+    pub fn unlock(&self, key: &KeyId, password: &str) -> Result<KeyInfo, KeystoreError> {
+        let key_file = self.resolve(key)?;
+        let suri = decrypt(&key_file, password)?;
+        let pair = Sr25519Pair::from_string(&suri, None).map_err(|_| KeystoreError::InvalidSeed)?;
+        self.unlocked
+            .write()
+            .unwrap()
+            .insert(key_file.key_id.clone(), pair);
+        Ok(key_info(&key_file, true))
+    }
+
+    pub fn lock(&self, key: &KeyId) -> Result<KeyInfo, KeystoreError> {
+        let key_file = self.resolve(key)?;
+        self.unlocked.write().unwrap().remove(&key_file.key_id);
+        Ok(key_info(&key_file, false))
+    }
+
+    /// Change the callers allowed to sign with a key, the password proves the key is theirs
+    pub fn bind(
+        &self,
+        key: &KeyId,
+        password: &str,
+        principals: Vec<String>,
+        min_role: Role,
+    ) -> Result<KeyInfo, KeystoreError> {
+        let mut key_file = self.resolve(key)?;
+        decrypt(&key_file, password)?;
+        key_file.principals = principals;
+        key_file.min_role = min_role;
+
+        let mut keys = self.keys.write().unwrap();
+        self.save(&key_file)?;
+        keys.insert(key_file.key_id.clone(), key_file.clone());
+        let unlocked = self.unlocked.read().unwrap().contains_key(&key_file.key_id);
+        Ok(key_info(&key_file, unlocked))
+    }
+
+    /// Get the signing pair of an unlocked key, by key id or alias, if the caller may use it
+    pub fn pair(&self, key: &KeyId, caller: &Caller) -> Result<Sr25519Pair, KeystoreError> {
+        let key_file = self.resolve(key)?;
+        if !key_file.allows(caller) {
+            return Err(KeystoreError::NotAllowed(key_file.alias));
+        }
+        match self.unlocked.read().unwrap().get(&key_file.key_id) {
+            Some(pair) => Ok(pair.clone()),
+            None => Err(KeystoreError::Locked(key_file.alias)),
+        }
+    }
+
+    fn resolve(&self, key: &KeyId) -> Result<KeyFile, KeystoreError> {
+        let keys = self.keys.read().unwrap();
+        keys.get(key.as_str())
+            .or_else(|| {
+                keys.values()
+                    .find(|key_file| key_file.alias == key.as_str())
+            })
+            .cloned()
+            .ok_or_else(|| KeystoreError::NotFound(String::from(key)))
+    }
+
+    fn insert(
+        &self,
+        alias: &str,
+        suri: &str,
+        password: &str,
+        principals: Vec<String>,
+        min_role: Role,
+    ) -> Result<KeyInfo, KeystoreError> {
+        let pair = Sr25519Pair::from_string(suri, None).map_err(|_| KeystoreError::InvalidSeed)?;
+        let key_id = format!("0x{}", hex::encode(pair.public().0));
+        let account = format!("{}", pair.public().into_account());
+
+        let mut keys = self.keys.write().unwrap();
+        if keys.contains_key(&key_id) {
+            return Err(KeystoreError::AlreadyExists(key_id));
+        }
+        if keys.values().any(|key_file| key_file.alias == alias) {
+            return Err(KeystoreError::AliasTaken(alias.to_string()));
+        }
+
+        let mut key_file = encrypt(key_id.clone(), alias.to_string(), account, suri, password)?;
+        key_file.principals = principals;
+        key_file.min_role = min_role;
+        self.save(&key_file)?;
+        keys.insert(key_id.clone(), key_file.clone());
+        self.unlocked.write().unwrap().insert(key_id, pair);
+        Ok(key_info(&key_file, true))
+    }
+
+    fn save(&self, key_file: &KeyFile) -> Result<(), KeystoreError> {
+        let file_path = self.path.join(format!("{}.json", key_file.key_id));
+        fs::write(&file_path, serde_json::to_vec_pretty(key_file)?)?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&file_path, fs::Permissions::from_mode(0o600))?;
+        }
+        Ok(())
+    }
+}
+
+fn key_info(key_file: &KeyFile, unlocked: bool) -> KeyInfo {
+    KeyInfo {
+        key_id: KeyId::from(key_file.key_id.clone()),
+        alias: key_file.alias.clone(),
+        account: Account::from(key_file.account.clone()),
+        locked: !unlocked,
+        principals: key_file.principals.clone(),
+        min_role: key_file.min_role,
+    }
+}
+
+fn derive_key(password: &str, salt: &[u8]) -> Result<[u8; 32], KeystoreError> {
+    let mut key = [0u8; 32];
+    Argon2::default()
+        .hash_password_into(password.as_bytes(), salt, &mut key)
+        .map_err(|e| KeystoreError::Crypto(e.to_string()))?;
+    Ok(key)
+}
+
+fn encrypt(
+    key_id: String,
+    alias: String,
+    account: String,
+    suri: &str,
+    password: &str,
+) -> Result<KeyFile, KeystoreError> {
+    let salt = rand::thread_rng().gen::<[u8; SALT_LEN]>();
+    let nonce = rand::thread_rng().gen::<[u8; NONCE_LEN]>();
+    let key = derive_key(password, &salt)?;
+    let cipher = ChaCha20Poly1305::new(Key::from_slice(&key));
+    let ciphertext = cipher
+        .encrypt(Nonce::from_slice(&nonce), suri.as_bytes())
+        .map_err(|e| KeystoreError::Crypto(e.to_string()))?;
+    Ok(KeyFile {
+        key_id,
+        alias,
+        account,
+        salt: hex::encode(salt),
+        nonce: hex::encode(nonce),
+        ciphertext: hex::encode(ciphertext),
+        principals: vec![],
+        min_role: Role::Sudo,
+    })
+}
+
+fn decrypt(key_file: &KeyFile, password: &str) -> Result<String, KeystoreError> {
+    let invalid = |_| KeystoreError::Crypto(format!("corrupted key file {}", key_file.key_id));
+    let salt = hex::decode(&key_file.salt).map_err(invalid)?;
+    let nonce = hex::decode(&key_file.nonce).map_err(invalid)?;
+    let ciphertext = hex::decode(&key_file.ciphertext).map_err(invalid)?;
+    let key = derive_key(password, &salt)?;
+    let cipher = ChaCha20Poly1305::new(Key::from_slice(&key));
+    let suri = cipher
+        .decrypt(Nonce::from_slice(&nonce), ciphertext.as_slice())
+        .map_err(|_| KeystoreError::InvalidPassword)?;
+    String::from_utf8(suri).map_err(|_| KeystoreError::InvalidSeed)
+}
+
+/// Create a new key in the keystore
+pub async fn create(
+    data: web::Data<AppState>,
+    caller: Caller,
+    req: web::Json<CreateKeyInput>,
+) -> error::Result<HttpResponse> {
+    let req = req.into_inner();
+    let key = data
+        .keystore
+        .create(
+            &req.alias,
+            &req.password,
+            principals(req.principals, &caller),
+            req.min_role.unwrap_or(Role::Sudo),
+        )
+        .map_err(map_keystore_err)?;
+    Ok(HttpResponse::Ok().json(CreateKeyOutput { key }))
+}
+
+/// Import an existing seed into the keystore
+pub async fn import(
+    data: web::Data<AppState>,
+    caller: Caller,
+    req: web::Json<ImportKeyInput>,
+) -> error::Result<HttpResponse> {
+    let req = req.into_inner();
+    let key = data
+        .keystore
+        .import(
+            &req.alias,
+            &req.seed,
+            &req.password,
+            principals(req.principals, &caller),
+            req.min_role.unwrap_or(Role::Sudo),
+        )
+        .map_err(map_keystore_err)?;
+    Ok(HttpResponse::Ok().json(ImportKeyOutput { key }))
+}
+
+/// Principals of a new key, the caller adding it when none are given
+fn principals(principals: Vec<String>, caller: &Caller) -> Vec<String> {
+    match (principals.is_empty(), &caller.principal) {
+        (true, Some(principal)) => vec![principal.clone()],
+        _ => principals,
+    }
+}
+
+/// Change the callers allowed to sign with a key
+pub async fn bind(
+    data: web::Data<AppState>,
+    req: web::Json<BindKeyInput>,
+) -> error::Result<HttpResponse> {
+    let req = req.into_inner();
+    let key = data
+        .keystore
+        .bind(&req.key, &req.password, req.principals, req.min_role)
+        .map_err(map_keystore_err)?;
+    Ok(HttpResponse::Ok().json(BindKeyOutput { key }))
+}
+
+/// List keys in the keystore and their lock state
+pub async fn list(data: web::Data<AppState>) -> error::Result<HttpResponse> {
+    Ok(HttpResponse::Ok().json(ListKeysOutput {
+        keys: data.keystore.list(),
+    }))
+}
+
+/// Unlock a key so it can be used to sign
+pub async fn unlock(
+    data: web::Data<AppState>,
+    req: web::Json<UnlockKeyInput>,
+) -> error::Result<HttpResponse> {
+    let key = data
+        .keystore
+        .unlock(&req.key, &req.password)
+        .map_err(map_keystore_err)?;
+    Ok(HttpResponse::Ok().json(UnlockKeyOutput { key }))
+}
+
+/// Lock a key, removing its decrypted secret from memory
+pub async fn lock(
+    data: web::Data<AppState>,
+    req: web::Json<LockKeyInput>,
+) -> error::Result<HttpResponse> {
+    let key = data.keystore.lock(&req.key).map_err(map_keystore_err)?;
+    Ok(HttpResponse::Ok().json(LockKeyOutput { key }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Empty keystore in its own temporary directory
+    fn keystore(name: &str) -> Keystore {
+        let path = std::env::temp_dir().join(format!(
+            "sugarfunge-keystore-{}-{}",
+            name,
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&path);
+        Keystore::open(&path).unwrap()
+    }
+
+    fn caller(principal: Option<&str>, role: Role) -> Caller {
+        Caller {
+            principal: principal.map(str::to_string),
+            role,
+        }
+    }
+
+    #[test]
+    fn encrypted_seeds_decrypt_only_with_their_password() {
+        let key_file = encrypt(
+            "0x01".into(),
+            "alice".into(),
+            "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY".into(),
+            "//Alice",
+            "secret",
+        )
+        .unwrap();
+        assert!(!key_file.ciphertext.contains(&hex::encode("//Alice")));
+        assert_eq!(decrypt(&key_file, "secret").unwrap(), "//Alice");
+        assert!(matches!(
+            decrypt(&key_file, "guess"),
+            Err(KeystoreError::InvalidPassword)
+        ));
+
+        // Each encryption draws its own salt and nonce
+        let again = encrypt(
+            "0x01".into(),
+            "alice".into(),
+            "".into(),
+            "//Alice",
+            "secret",
+        )
+        .unwrap();
+        assert_ne!(again.salt, key_file.salt);
+        assert_ne!(again.ciphertext, key_file.ciphertext);
+    }
+
+    #[test]
+    fn keys_are_loaded_locked_from_their_files() {
+        let keystore = keystore("load");
+        let seed = Seed::from("//Alice".to_string());
+        let created = keystore
+            .import("alice", &seed, "secret", vec![], Role::Sudo)
+            .unwrap();
+        assert_eq!(
+            created.account.as_str(),
+            "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY"
+        );
+
+        let reopened = Keystore::open(&keystore.path).unwrap();
+        let key = KeyId::from("alice".to_string());
+        let sudo = caller(None, Role::Sudo);
+        let loaded = reopened.list();
+        assert_eq!(loaded.len(), 1);
+        assert_eq!(loaded[0].key_id.as_str(), created.key_id.as_str());
+        assert!(loaded[0].locked);
+        assert!(matches!(
+            reopened.pair(&key, &sudo),
+            Err(KeystoreError::Locked(_))
+        ));
+        assert!(matches!(
+            reopened.unlock(&key, "guess"),
+            Err(KeystoreError::InvalidPassword)
+        ));
+
+        reopened.unlock(&key, "secret").unwrap();
+        let pair = reopened.pair(&key, &sudo).unwrap();
+        assert_eq!(
+            format!("0x{}", hex::encode(pair.public().0)),
+            created.key_id.as_str()
+        );
+    }
+
+    #[test]
+    fn keys_sign_only_for_their_principals_and_roles() {
+        let keystore = keystore("binding");
+        let key = KeyId::from("treasury".to_string());
+        keystore
+            .create("treasury", "secret", vec!["ops".into()], Role::Sudo)
+            .unwrap();
+
+        assert!(keystore
+            .pair(&key, &caller(Some("ops"), Role::User))
+            .is_ok());
+        assert!(keystore.pair(&key, &caller(None, Role::Sudo)).is_ok());
+        for caller in [
+            caller(Some("dashboard"), Role::User),
+            caller(None, Role::Operator),
+        ] {
+            assert!(matches!(
+                keystore.pair(&key, &caller),
+                Err(KeystoreError::NotAllowed(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn binding_a_key_needs_its_password() {
+        let keystore = keystore("bind");
+        let key = KeyId::from("treasury".to_string());
+        keystore
+            .create("treasury", "secret", vec![], Role::Sudo)
+            .unwrap();
+        let dashboard = caller(Some("dashboard"), Role::User);
+
+        assert!(matches!(
+            keystore.bind(&key, "guess", vec!["dashboard".into()], Role::Sudo),
+            Err(KeystoreError::InvalidPassword)
+        ));
+        assert!(keystore.pair(&key, &dashboard).is_err());
+
+        let info = keystore
+            .bind(&key, "secret", vec!["dashboard".into()], Role::Sudo)
+            .unwrap();
+        assert_eq!(info.principals, vec!["dashboard".to_string()]);
+        assert!(keystore.pair(&key, &dashboard).is_ok());
+    }
+}
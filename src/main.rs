@@ -1,11 +1,13 @@
-use actix_cors::Cors;
+use actix::Actor;
 use actix_web::{
-    http, middleware,
+    dev::Service,
+    middleware,
     web::{self, Data},
     App, HttpServer,
 };
 use args::*;
 use clap::Parser;
+use futures::future::{ready, Either};
 use state::*;
 use std::sync::Arc;
 use subxt::{
@@ -17,19 +19,35 @@ use subxt::{
 mod account;
 mod args;
 mod asset;
+mod auth;
 mod bag;
+mod batch;
 mod bundle;
+mod cache;
 mod challenge;
+mod compat;
 mod config;
 mod contract;
+mod dynamic;
+mod error;
+mod events;
 mod fula;
+mod keystore;
 mod market;
+mod multisig;
+mod node;
+mod nonce;
 mod online;
 mod pool;
+mod proxy;
+mod server;
+mod sse;
 mod state;
 mod subscription;
+mod tx;
 mod util;
 mod validator;
+mod webhook;
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
@@ -37,46 +55,126 @@ async fn main() -> std::io::Result<()> {
 
     let args = Args::parse();
 
-    let rpc_client = RpcClient::from_url(args.node_server.to_string())
+    let server_config = server::ServerConfig::load(&args)?;
+
+    let node = node::NodeClient::connect(&args.node_server)
         .await
         .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e.to_string()))?;
 
+    let rpc_client = RpcClient::new(node.clone());
+
     let api = OnlineClient::<PolkadotConfig>::from_rpc_client(rpc_client.clone())
         .await
         .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e.to_string()))?;
 
     let rpc = LegacyRpcMethods::<PolkadotConfig>::new(rpc_client.clone());
 
+    let compat = compat::Compat::new()
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e.to_string()))?;
+
+    let keystore = keystore::Keystore::open(&args.keystore)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e.to_string()))?;
+
+    let auth_config = Arc::new(auth::AuthConfig::load(args.auth_config.as_deref())?);
+    if !auth_config.enabled() {
+        if !args.insecure_no_auth {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "No API keys or JWT secret configured, pass --insecure-no-auth to run without auth",
+            ));
+        }
+        log::warn!("Authentication is disabled by --insecure-no-auth");
+    }
+
+    let webhooks = webhook::Webhooks::new(args.webhook_hosts.clone())
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e.to_string()))?;
+
+    let api = Arc::new(api);
+    let rpc = Arc::new(rpc);
+
     let state = AppState {
-        api: Arc::new(api),
-        node_url: args.node_server.to_string(),
-        rpc: Arc::new(rpc),
+        api: api.clone(),
+        node,
+        rpc: rpc.clone(),
+        keystore: Arc::new(keystore),
+        allow_seed: !args.disable_seed,
+        txs: Arc::new(tx::TxTracker::default()),
+        payloads: Arc::new(tx::PayloadTracker::default()),
+        nonces: Arc::new(nonce::NonceManager::default()),
+        cache: Arc::new(cache::Cache::new(cache::CacheConfig {
+            max_bytes: args.cache_max_bytes,
+            routes: args.cache_routes.clone(),
+        })),
+        compat: Arc::new(compat),
+        events: events::EventBus::new(api, rpc).start(),
+        webhooks: Arc::new(webhooks),
     };
 
-    HttpServer::new(move || {
-        let cors = Cors::default()
-            .allowed_origin("http://localhost:8080")
-            .allowed_origin_fn(|origin, _req_head| {
-                origin.as_bytes().starts_with(b"http://localhost")
-            })
-            .allowed_methods(vec!["GET", "POST"])
-            .allowed_headers(vec![http::header::AUTHORIZATION, http::header::ACCEPT])
-            .allowed_header(http::header::CONTENT_TYPE)
-            .max_age(3600);
+    state.compat.check(&state.api);
+
+    actix_web::rt::spawn(node::follow_runtime_upgrades(
+        state.api.clone(),
+        state.compat.clone(),
+    ));
+
+    if state.cache.enabled() {
+        actix_web::rt::spawn(cache::follow_finalized(
+            state.api.clone(),
+            state.cache.clone(),
+        ));
+    }
+
+    let cors_config = server_config.cors.clone();
+    let body_limit = server_config.body_limit;
+    let server = HttpServer::new(move || {
+        let compat = state.compat.clone();
+        let cors = cors_config.cors();
 
         App::new()
+            .wrap_fn(move |req, srv| match compat.refusal(req.path()) {
+                Some(e) => Either::Left(ready(Err(e.into()))),
+                None => Either::Right(srv.call(req)),
+            })
+            .wrap(auth::Auth::new(auth_config.clone()))
             .wrap(middleware::Compress::default())
-            .wrap(middleware::Logger::default())
+            .wrap(
+                middleware::Logger::new(auth::LOG_FORMAT)
+                    .custom_request_replace("request_line", auth::redacted_request_line),
+            )
             .wrap(cors)
             .app_data(Data::new(state.clone()))
+            .app_data(web::PayloadConfig::new(body_limit))
+            .app_data(
+                web::JsonConfig::default()
+                    .limit(body_limit)
+                    .error_handler(|e, _req| error::ApiError::InvalidInput(e.to_string()).into()),
+            )
             .service(web::resource("/ws").route(web::get().to(subscription::ws)))
+            .route("events", web::get().to(sse::events))
             .route("health", web::post().to(util::health_check))
+            .route("compat", web::post().to(compat::status))
+            .route("dynamic/tx", web::post().to(dynamic::tx))
+            .route("batch", web::post().to(batch::batch))
+            .route("multisig/account", web::post().to(multisig::account))
+            .route("multisig/open", web::post().to(multisig::open))
+            .route("multisig/approve", web::post().to(multisig::approve))
+            .route("multisig/pending", web::post().to(multisig::pending))
+            .route("proxy", web::post().to(proxy::proxy))
+            .route("dynamic/storage", web::post().to(dynamic::storage))
+            .route("tx/status", web::post().to(tx::status))
+            .route("tx/submit_signed", web::post().to(tx::submit_signed))
             .route("account/seeded", web::post().to(account::seeded))
             .route("account/exists", web::post().to(account::exists))
             .route("account/create", web::post().to(account::create))
             .route("account/fund", web::post().to(account::fund))
             .route("account/set_balance", web::post().to(account::set_balance))
             .route("account/balance", web::post().to(account::balance))
+            .route("keystore/create", web::post().to(keystore::create))
+            .route("keystore/import", web::post().to(keystore::import))
+            .route("keystore/list", web::post().to(keystore::list))
+            .route("keystore/unlock", web::post().to(keystore::unlock))
+            .route("keystore/lock", web::post().to(keystore::lock))
+            .route("keystore/bind", web::post().to(keystore::bind))
             .route("asset/create_class", web::post().to(asset::create_class))
             .route("asset/class_info", web::post().to(asset::class_info))
             .route("asset/create", web::post().to(asset::create))
@@ -261,8 +359,12 @@ async fn main() -> std::io::Result<()> {
                 "fula/online/get_heartbeat_time",
                 web::post().to(online::get_heartbeat_time),
             )
-    })
-    .bind((args.listen.host_str().unwrap(), args.listen.port().unwrap()))?
-    .run()
-    .await
+    });
+
+    let address = server_config.address()?;
+    let server = match server_config.rustls()? {
+        Some(tls) => server.bind_rustls_021(address, tls)?,
+        None => server.bind(address)?,
+    };
+    server.run().await
 }
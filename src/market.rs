@@ -1,8 +1,9 @@
+use crate::auth::Caller;
+use crate::error::ApiError;
 use crate::state::*;
+use crate::tx::{respond, submit, TxOptions};
 use crate::util::*;
 use actix_web::{error, web, HttpResponse};
-use serde_json::json;
-use subxt::tx::PairSigner;
 use sugarfunge_api_types::market::*;
 use sugarfunge_api_types::primitives::*;
 use sugarfunge_api_types::sugarfunge;
@@ -38,47 +39,39 @@ fn transform_balances(
 
 pub async fn create_market(
     data: web::Data<AppState>,
+    caller: Caller,
     req: web::Json<CreateMarketInput>,
 ) -> error::Result<HttpResponse> {
-    let pair = get_pair_from_seed(&req.seed)?;
-    let signer = PairSigner::new(pair);
-    let api = &data.api;
-
+    let signer = get_signer(&data, &caller, &req.tx)?;
     let call = sugarfunge::tx()
         .market()
         .create_market(req.market_id.into());
 
-    let result = api
-        .tx()
-        .sign_and_submit_then_watch(&call, &signer, Default::default())
-        .await
-        .map_err(map_subxt_err)?
-        .wait_for_finalized_success()
-        .await
-        .map_err(map_sf_err)?;
-    let result = result
-        .find_first::<sugarfunge::market::events::Created>()
-        .map_err(map_subxt_err)?;
-    match result {
-        Some(event) => Ok(HttpResponse::Ok().json(CreateMarketOutput {
-            who: event.who.into(),
-            market_id: event.market_id.into(),
-        })),
-        None => Ok(HttpResponse::BadRequest().json(RequestError {
-            message: json!("Failed to find sugarfunge::market::events::Created"),
-            description: String::new(),
-        })),
-    }
+    let options = TxOptions::new(&req.tx);
+    respond(submit(&data, &call, &signer, options).await?, |receipt| {
+        let result = receipt
+            .events
+            .find_first::<sugarfunge::market::events::Created>()
+            .map_err(map_subxt_err)?;
+        match result {
+            Some(event) => Ok(receipt.json(CreateMarketOutput {
+                who: event.who.into(),
+                market_id: event.market_id.into(),
+            })),
+            None => {
+                let event = "sugarfunge::market::events::Created";
+                Err(ApiError::EventNotFound(event.into()).into())
+            }
+        }
+    })
 }
 
 pub async fn create_market_rate(
     data: web::Data<AppState>,
+    caller: Caller,
     req: web::Json<CreateMarketRateInput>,
 ) -> error::Result<HttpResponse> {
-    let pair = get_pair_from_seed(&req.seed)?;
-    let signer = PairSigner::new(pair);
-    let api = &data.api;
-
+    let signer = get_signer(&data, &caller, &req.tx)?;
     let rates = &req.rates.rates; //transform_input(&req.rates.rates);
     let rates = extrinsinc_rates(rates);
 
@@ -88,108 +81,92 @@ pub async fn create_market_rate(
         rates,
     );
 
-    let result = api
-        .tx()
-        .sign_and_submit_then_watch(&call, &signer, Default::default())
-        .await
-        .map_err(map_subxt_err)?
-        .wait_for_finalized_success()
-        .await
-        .map_err(map_sf_err)?;
-    let result = result
-        .find_first::<sugarfunge::market::events::RateCreated>()
-        .map_err(map_subxt_err)?;
-    match result {
-        Some(event) => Ok(HttpResponse::Ok().json(CreateMarketRateOutput {
-            who: event.who.into(),
-            market_id: event.market_id.into(),
-            market_rate_id: MarketId::from(event.market_rate_id),
-        })),
-        None => Ok(HttpResponse::BadRequest().json(RequestError {
-            message: json!("Failed to find sugarfunge::market::events::RateCreated"),
-            description: String::new(),
-        })),
-    }
+    let options = TxOptions::new(&req.tx);
+    respond(submit(&data, &call, &signer, options).await?, |receipt| {
+        let result = receipt
+            .events
+            .find_first::<sugarfunge::market::events::RateCreated>()
+            .map_err(map_subxt_err)?;
+        match result {
+            Some(event) => Ok(receipt.json(CreateMarketRateOutput {
+                who: event.who.into(),
+                market_id: event.market_id.into(),
+                market_rate_id: MarketId::from(event.market_rate_id),
+            })),
+            None => {
+                let event = "sugarfunge::market::events::RateCreated";
+                Err(ApiError::EventNotFound(event.into()).into())
+            }
+        }
+    })
 }
 
 pub async fn deposit_assets(
     data: web::Data<AppState>,
+    caller: Caller,
     req: web::Json<DepositAssetsInput>,
 ) -> error::Result<HttpResponse> {
-    let pair = get_pair_from_seed(&req.seed)?;
-    let signer = PairSigner::new(pair);
-    let api = &data.api;
-
+    let signer = get_signer(&data, &caller, &req.tx)?;
     let call = sugarfunge::tx().market().deposit(
         req.market_id.into(),
         u64::from(req.market_rate_id),
         req.amount.into(),
     );
 
-    let result = api
-        .tx()
-        .sign_and_submit_then_watch(&call, &signer, Default::default())
-        .await
-        .map_err(map_subxt_err)?
-        .wait_for_finalized_success()
-        .await
-        .map_err(map_sf_err)?;
-    let result = result
-        .find_first::<sugarfunge::market::events::Deposit>()
-        .map_err(map_subxt_err)?;
-    match result {
-        Some(event) => Ok(HttpResponse::Ok().json(DepositAssetsOutput {
-            who: event.who.into(),
-            market_id: event.market_id.into(),
-            market_rate_id: MarketId::from(event.market_rate_id),
-            amount: event.amount.into(),
-            balances: transform_balances(event.balances),
-            success: event.success,
-        })),
-        None => Ok(HttpResponse::BadRequest().json(RequestError {
-            message: json!("Failed to find sugarfunge::market::events::Deposit"),
-            description: String::new(),
-        })),
-    }
+    let options = TxOptions::new(&req.tx);
+    respond(submit(&data, &call, &signer, options).await?, |receipt| {
+        let result = receipt
+            .events
+            .find_first::<sugarfunge::market::events::Deposit>()
+            .map_err(map_subxt_err)?;
+        match result {
+            Some(event) => Ok(receipt.json(DepositAssetsOutput {
+                who: event.who.into(),
+                market_id: event.market_id.into(),
+                market_rate_id: MarketId::from(event.market_rate_id),
+                amount: event.amount.into(),
+                balances: transform_balances(event.balances),
+                success: event.success,
+            })),
+            None => {
+                let event = "sugarfunge::market::events::Deposit";
+                Err(ApiError::EventNotFound(event.into()).into())
+            }
+        }
+    })
 }
 
 pub async fn exchange_assets(
     data: web::Data<AppState>,
+    caller: Caller,
     req: web::Json<ExchangeAssetsInput>,
 ) -> error::Result<HttpResponse> {
-    let pair = get_pair_from_seed(&req.seed)?;
-    let signer = PairSigner::new(pair);
-    let api = &data.api;
-
+    let signer = get_signer(&data, &caller, &req.tx)?;
     let call = sugarfunge::tx().market().exchange_assets(
         req.market_id.into(),
         u64::from(req.market_rate_id),
         req.amount.into(),
     );
 
-    let result = api
-        .tx()
-        .sign_and_submit_then_watch(&call, &signer, Default::default())
-        .await
-        .map_err(map_subxt_err)?
-        .wait_for_finalized_success()
-        .await
-        .map_err(map_sf_err)?;
-    let result = result
-        .find_first::<sugarfunge::market::events::Exchanged>()
-        .map_err(map_subxt_err)?;
-    match result {
-        Some(event) => Ok(HttpResponse::Ok().json(ExchangeAssetsOutput {
-            buyer: event.buyer.into(),
-            market_id: event.market_id.into(),
-            market_rate_id: MarketId::from(event.market_rate_id),
-            amount: event.amount.into(),
-            balances: transform_balances(event.balances),
-            success: event.success,
-        })),
-        None => Ok(HttpResponse::BadRequest().json(RequestError {
-            message: json!("Failed to find sugarfunge::market::events::Exchange"),
-            description: String::new(),
-        })),
-    }
+    let options = TxOptions::new(&req.tx);
+    respond(submit(&data, &call, &signer, options).await?, |receipt| {
+        let result = receipt
+            .events
+            .find_first::<sugarfunge::market::events::Exchanged>()
+            .map_err(map_subxt_err)?;
+        match result {
+            Some(event) => Ok(receipt.json(ExchangeAssetsOutput {
+                buyer: event.buyer.into(),
+                market_id: event.market_id.into(),
+                market_rate_id: MarketId::from(event.market_rate_id),
+                amount: event.amount.into(),
+                balances: transform_balances(event.balances),
+                success: event.success,
+            })),
+            None => {
+                let event = "sugarfunge::market::events::Exchange";
+                Err(ApiError::EventNotFound(event.into()).into())
+            }
+        }
+    })
 }
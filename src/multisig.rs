@@ -0,0 +1,315 @@
+use crate::auth::Caller;
+use crate::batch::{dispatch_error, to_runtime_call};
+use crate::error::ApiError;
+use crate::state::*;
+use crate::tx::{call_weight, decode_events, respond, submit, TxOptions};
+use crate::util::*;
+use actix_web::{error, web, HttpResponse};
+use codec::{Decode, Encode};
+use subxt::dynamic::Value;
+use subxt::utils::AccountId32;
+use sugarfunge_api_types::multisig::*;
+
+const MULTISIG_PALLET: &str = "Multisig";
+
+/// Prefix the pallet hashes with the signatories and threshold into the multisig account id
+const MULTISIG_ENTROPY: &[u8; 16] = b"modlpy/utilisuba";
+
+/// Offset of the dispatch result in the fields of `MultisigExecuted`, after the approving
+/// account, the timepoint, the multisig account and the call hash
+const EXECUTED_RESULT_OFFSET: usize = 32 + 8 + 32 + 32;
+
+/// `pallet_multisig::Multisig` stored for each open operation
+#[derive(Decode)]
+struct Multisig {
+    when: Timepoint,
+    deposit: u128,
+    depositor: AccountId32,
+    approvals: Vec<AccountId32>,
+}
+
+#[derive(Decode, Clone, Copy)]
+struct Timepoint {
+    height: u32,
+    index: u32,
+}
+
+/// Derives the account controlled by `threshold` of the `signatories`
+pub async fn account(
+    data: web::Data<AppState>,
+    req: web::Json<MultisigAccountInput>,
+) -> error::Result<HttpResponse> {
+    multisig_pallet(&data)?;
+    let mut signatories = req
+        .signatories
+        .iter()
+        .map(AccountId32::try_from)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(map_account_err)?;
+    let account = multisig_account(&mut signatories, req.threshold)?;
+    Ok(HttpResponse::Ok().json(MultisigAccountOutput {
+        account: account.into(),
+        signatories: signatories
+            .into_iter()
+            .map(|signatory| signatory.into())
+            .collect(),
+        threshold: req.threshold,
+    }))
+}
+
+/// Opens a multisig operation with the approval of the signer
+pub async fn open(
+    data: web::Data<AppState>,
+    caller: Caller,
+    req: web::Json<MultisigCallInput>,
+) -> error::Result<HttpResponse> {
+    approve_call(&data, &caller, &req, true).await
+}
+
+/// Approves an open multisig operation, the call is dispatched with the last approval
+pub async fn approve(
+    data: web::Data<AppState>,
+    caller: Caller,
+    req: web::Json<MultisigCallInput>,
+) -> error::Result<HttpResponse> {
+    approve_call(&data, &caller, &req, false).await
+}
+
+/// Lists the operations of a multisig account waiting for approvals
+pub async fn pending(
+    data: web::Data<AppState>,
+    req: web::Json<MultisigPendingInput>,
+) -> error::Result<HttpResponse> {
+    multisig_pallet(&data)?;
+    let account = AccountId32::try_from(&req.account).map_err(map_account_err)?;
+    let address =
+        subxt::dynamic::storage(MULTISIG_PALLET, "Multisigs", vec![account_value(&account)]);
+    let prefix = data
+        .api
+        .storage()
+        .address_bytes(&address)
+        .map_err(map_subxt_err)?;
+
+    let block = block_at(&data, &req.at).await?;
+    let (entries, next_cursor) =
+        fetch_entries_page(&data, &block, None, prefix, req.limit, &req.cursor).await?;
+    let pending = entries
+        .iter()
+        .map(|(key, value)| {
+            let multisig =
+                Multisig::decode(&mut &value[..]).map_err(|e| ApiError::Decoding(e.to_string()))?;
+            // The call hash is the last key, hashed with Blake2_128Concat
+            Ok(MultisigPending {
+                call_hash: format!("0x{}", hex::encode(&key[key.len().saturating_sub(32)..])),
+                when: MultisigTimepoint {
+                    height: multisig.when.height,
+                    index: multisig.when.index,
+                },
+                deposit: multisig.deposit.into(),
+                depositor: multisig.depositor.into(),
+                approvals: multisig.approvals.into_iter().map(|a| a.into()).collect(),
+            })
+        })
+        .collect::<error::Result<Vec<_>>>()?;
+    Ok(ok_at(&block).json(MultisigPendingOutput {
+        pending,
+        next_cursor,
+    }))
+}
+
+/// Submits `Multisig::as_multi` for the call of the input. Opening requires no operation for the
+/// call yet, approving requires one and passes the timepoint it was opened at.
+async fn approve_call(
+    data: &AppState,
+    caller: &Caller,
+    req: &MultisigCallInput,
+    opening: bool,
+) -> error::Result<HttpResponse> {
+    multisig_pallet(data)?;
+    let signer = get_signer(data, caller, &req.tx)?;
+    let mut others = req
+        .other_signatories
+        .iter()
+        .map(AccountId32::try_from)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(map_account_err)?;
+    // A proxied approval is the approval of the account the delegate acts for
+    let approver = match &req.tx.on_behalf_of {
+        Some(real) => AccountId32::try_from(real).map_err(map_account_err)?,
+        None => signer.account_id().clone(),
+    };
+    let mut signatories = others.clone();
+    signatories.push(approver);
+    let account = multisig_account(&mut signatories, req.threshold)?;
+    others.sort();
+
+    let call_data = to_runtime_call(data, &req.call)?.encode();
+    let call_hash = hash(&call_data);
+    let block = block_at(data, &None).await?;
+    let address = subxt::dynamic::storage(
+        MULTISIG_PALLET,
+        "Multisigs",
+        vec![account_value(&account), Value::from_bytes(call_hash.0)],
+    );
+    let key = data
+        .api
+        .storage()
+        .address_bytes(&address)
+        .map_err(map_subxt_err)?;
+    let open = match fetch_values(data, &block, None, vec![key]).await?.pop() {
+        Some((_, value)) => {
+            Some(Multisig::decode(&mut &value[..]).map_err(|e| ApiError::Decoding(e.to_string()))?)
+        }
+        None => None,
+    };
+    let timepoint = match (opening, open) {
+        (true, None) => None,
+        (true, Some(_)) => {
+            return Err(ApiError::InvalidInput(format!(
+                "Operation {:?} is already open, approve it instead",
+                call_hash
+            ))
+            .into())
+        }
+        (false, Some(open)) => Some(open.when),
+        (false, None) => {
+            return Err(ApiError::NotFound(format!("No open operation {:?}", call_hash)).into())
+        }
+    };
+
+    let call = call_value(data, &call_data)?;
+    let weight = call_weight(data, &call_data).await?;
+    let timepoint = match timepoint {
+        Some(when) => Value::unnamed_variant(
+            "Some",
+            [Value::named_composite([
+                ("height", Value::u128(when.height.into())),
+                ("index", Value::u128(when.index.into())),
+            ])],
+        ),
+        None => Value::unnamed_variant("None", []),
+    };
+    let max_weight = Value::named_composite([
+        ("ref_time", Value::u128(weight.ref_time.into())),
+        ("proof_size", Value::u128(weight.proof_size.into())),
+    ]);
+    let call = subxt::dynamic::tx(
+        MULTISIG_PALLET,
+        "as_multi",
+        vec![
+            Value::u128(req.threshold.into()),
+            Value::unnamed_composite(others.iter().map(account_value)),
+            timepoint,
+            call,
+            max_weight,
+        ],
+    );
+
+    let options = TxOptions::new(&req.tx);
+    respond(submit(data, &call, &signer, options).await?, |receipt| {
+        let mut outcome = None;
+        for event in receipt.events.iter() {
+            let event = event.map_err(map_subxt_err)?;
+            if event.pallet_name() != MULTISIG_PALLET {
+                continue;
+            }
+            outcome = match event.variant_name() {
+                "NewMultisig" => Some((MultisigState::Opened, None)),
+                "MultisigApproval" => Some((MultisigState::Approved, None)),
+                "MultisigExecuted" => {
+                    let result = event
+                        .field_bytes()
+                        .get(EXECUTED_RESULT_OFFSET..)
+                        .unwrap_or(&[]);
+                    let error = match result.split_first() {
+                        Some((&1, error)) => Some(dispatch_error(data, error)),
+                        _ => None,
+                    };
+                    Some((MultisigState::Executed, error))
+                }
+                _ => continue,
+            };
+        }
+        let (state, error) =
+            outcome.ok_or_else(|| ApiError::EventNotFound("Multisig event".into()))?;
+        Ok(receipt.json(MultisigCallOutput {
+            account: account.into(),
+            call_hash: format!("{:?}", call_hash),
+            state,
+            error,
+            events: decode_events(&receipt.events),
+        }))
+    })
+}
+
+/// The multisig routes encode their calls against the live metadata, the runtime needs the pallet
+fn multisig_pallet(data: &AppState) -> error::Result<()> {
+    match data.api.metadata().pallet_by_name(MULTISIG_PALLET) {
+        Some(_) => Ok(()),
+        None => Err(ApiError::IncompatibleRuntime(
+            "The runtime has no Multisig pallet, multisig accounts are not supported".into(),
+        )
+        .into()),
+    }
+}
+
+/// Account id derived by the pallet for `threshold` of the `signatories`, which are sorted in
+/// place as the pallet expects them
+fn multisig_account(
+    signatories: &mut Vec<AccountId32>,
+    threshold: u16,
+) -> error::Result<AccountId32> {
+    let len = signatories.len();
+    signatories.sort();
+    signatories.dedup();
+    if signatories.len() != len {
+        return Err(ApiError::InvalidInput("Signatories must be distinct".into()).into());
+    }
+    if threshold < 2 || usize::from(threshold) > len {
+        return Err(ApiError::InvalidInput(format!(
+            "Threshold must be between 2 and the {} signatories",
+            len
+        ))
+        .into());
+    }
+    let entropy = (MULTISIG_ENTROPY, &signatories[..], threshold).encode();
+    Ok(AccountId32::from(hash(&entropy).0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    const ALICE: &str = "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY";
+    const BOB: &str = "5FHneW46xGXgs5mUiveU4sbTyGBzmstUspZC92UhjJM694ty";
+    const CHARLIE: &str = "5FLSigC9HGRKVhB9FiEo4Y3koPsNmBmLJbpXg2mp1hXcS59Y";
+
+    fn accounts(addresses: &[&str]) -> Vec<AccountId32> {
+        addresses
+            .iter()
+            .map(|address| AccountId32::from_str(address).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn derives_the_pallet_multisig_account() {
+        // Alice, Bob and Charlie with a threshold of 2, as derived by polkadot.js
+        let expected = "5DjYJStmdZ2rcqXbXGX7TW85JsrW6uG4y9MUcLq2BoPMpRA7";
+        let mut signatories = accounts(&[ALICE, BOB, CHARLIE]);
+        let account = multisig_account(&mut signatories, 2).unwrap();
+        assert_eq!(account.to_string(), expected);
+
+        // The order signatories are given in doesn't matter
+        let mut signatories = accounts(&[CHARLIE, ALICE, BOB]);
+        let account = multisig_account(&mut signatories, 2).unwrap();
+        assert_eq!(account.to_string(), expected);
+    }
+
+    #[test]
+    fn rejects_duplicate_signatories_and_bad_thresholds() {
+        assert!(multisig_account(&mut accounts(&[ALICE, BOB, ALICE]), 2).is_err());
+        assert!(multisig_account(&mut accounts(&[ALICE, BOB]), 1).is_err());
+        assert!(multisig_account(&mut accounts(&[ALICE, BOB]), 3).is_err());
+    }
+}
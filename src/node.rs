@@ -0,0 +1,203 @@
+use crate::compat::Compat;
+use crate::state::ClientAPI;
+use futures::lock::Mutex;
+use serde::Serialize;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use subxt::backend::rpc::{RawRpcFuture, RawRpcSubscription, RawValue, RpcClient, RpcClientT};
+use subxt::error::RpcError;
+use url::Url;
+
+/// How long to wait before following runtime upgrades again when the subscription ends
+const RESUBSCRIBE_DELAY: Duration = Duration::from_secs(5);
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ConnectionState {
+    Connected,
+    Reconnecting,
+    Disconnected,
+}
+
+struct Active {
+    index: usize,
+    client: RpcClient,
+    state: ConnectionState,
+    /// Bumped on every reconnect, so concurrent failures reconnect only once
+    generation: u64,
+}
+
+struct Inner {
+    urls: Vec<Url>,
+    active: RwLock<Active>,
+    reconnecting: Mutex<()>,
+}
+
+/// RPC client over a list of node endpoints. Requests go to the active endpoint, when its
+/// connection is lost the client fails over to the next endpoint that accepts a connection.
+#[derive(Clone)]
+pub struct NodeClient {
+    inner: Arc<Inner>,
+}
+
+impl NodeClient {
+    /// Connects to the first endpoint of `urls` that accepts a connection
+    pub async fn connect(urls: &[Url]) -> Result<Self, RpcError> {
+        let (index, client) = connect_any(urls, 0).await?;
+        Ok(NodeClient {
+            inner: Arc::new(Inner {
+                urls: urls.to_vec(),
+                active: RwLock::new(Active {
+                    index,
+                    client,
+                    state: ConnectionState::Connected,
+                    generation: 0,
+                }),
+                reconnecting: Mutex::new(()),
+            }),
+        })
+    }
+
+    /// Url of the active endpoint and the state of its connection
+    pub fn status(&self) -> (Url, ConnectionState) {
+        let active = self.inner.active.read().unwrap();
+        (self.inner.urls[active.index].clone(), active.state)
+    }
+
+    fn current(&self) -> (RpcClient, u64) {
+        let active = self.inner.active.read().unwrap();
+        (active.client.clone(), active.generation)
+    }
+
+    /// Connects to the next endpoint after the connection of `generation` was lost. Does nothing
+    /// if another request already reconnected since.
+    async fn reconnect(&self, generation: u64) -> Result<RpcClient, RpcError> {
+        let _reconnecting = self.inner.reconnecting.lock().await;
+        let index = {
+            let mut active = self.inner.active.write().unwrap();
+            if active.generation != generation {
+                return Ok(active.client.clone());
+            }
+            active.state = ConnectionState::Reconnecting;
+            active.index
+        };
+        let (url, _) = self.status();
+        log::warn!("Lost connection to {}, reconnecting", url);
+
+        let start = (index + 1) % self.inner.urls.len();
+        let mut active = match connect_any(&self.inner.urls, start).await {
+            Ok((index, client)) => {
+                let mut active = self.inner.active.write().unwrap();
+                active.index = index;
+                active.client = client;
+                active.state = ConnectionState::Connected;
+                active
+            }
+            Err(e) => {
+                self.inner.active.write().unwrap().state = ConnectionState::Disconnected;
+                return Err(e);
+            }
+        };
+        active.generation += 1;
+        log::info!("Connected to {}", self.inner.urls[active.index]);
+        Ok(active.client.clone())
+    }
+}
+
+/// Tries every endpoint once, starting at `start`
+async fn connect_any(urls: &[Url], start: usize) -> Result<(usize, RpcClient), RpcError> {
+    let mut last_error = RpcError::RequestRejected("No node endpoint configured".into());
+    for offset in 0..urls.len() {
+        let index = (start + offset) % urls.len();
+        match RpcClient::from_url(urls[index].as_str()).await {
+            Ok(client) => return Ok((index, client)),
+            Err(e) => {
+                log::warn!("Could not connect to {}: {}", urls[index], e);
+                last_error = RpcError::ClientError(Box::new(e));
+            }
+        }
+    }
+    Err(last_error)
+}
+
+/// Whether `e` means the connection is gone rather than the node rejecting the call
+fn is_connection_error(e: &RpcError) -> bool {
+    match e {
+        RpcError::ClientError(e) => {
+            let e = e.to_string();
+            e.contains("restart required")
+                || e.contains("Networking or low-level protocol error")
+                || e.contains("Connection closed")
+        }
+        RpcError::SubscriptionDropped => true,
+        _ => false,
+    }
+}
+
+impl RpcClientT for NodeClient {
+    fn request_raw<'a>(
+        &'a self,
+        method: &'a str,
+        params: Option<Box<RawValue>>,
+    ) -> RawRpcFuture<'a, Box<RawValue>> {
+        Box::pin(async move {
+            let (client, generation) = self.current();
+            match client.request_raw(method, params.clone()).await {
+                Err(e) if is_connection_error(&e) => {
+                    let client = self.reconnect(generation).await?;
+                    client.request_raw(method, params).await
+                }
+                result => result,
+            }
+        })
+    }
+
+    fn subscribe_raw<'a>(
+        &'a self,
+        sub: &'a str,
+        params: Option<Box<RawValue>>,
+        unsub: &'a str,
+    ) -> RawRpcFuture<'a, RawRpcSubscription> {
+        Box::pin(async move {
+            let (client, generation) = self.current();
+            match client.subscribe_raw(sub, params.clone(), unsub).await {
+                Err(e) if is_connection_error(&e) => {
+                    let client = self.reconnect(generation).await?;
+                    client.subscribe_raw(sub, params, unsub).await
+                }
+                result => result,
+            }
+        })
+    }
+}
+
+/// Applies runtime upgrades as they are finalized so the client always encodes and decodes
+/// against the metadata of the running runtime, then checks the new runtime for compatibility
+pub async fn follow_runtime_upgrades(api: ClientAPI, compat: Arc<Compat>) {
+    let updater = api.updater();
+    loop {
+        match updater.runtime_updates().await {
+            Ok(mut updates) => {
+                while let Some(update) = updates.next().await {
+                    let update = match update {
+                        Ok(update) => update,
+                        Err(e) => {
+                            log::warn!("Runtime updates subscription failed: {}", e);
+                            break;
+                        }
+                    };
+                    let spec_version = update.runtime_version().spec_version;
+                    match updater.apply_update(update) {
+                        Ok(()) => {
+                            log::info!("Runtime upgraded to spec version {}", spec_version);
+                            compat.check(&api);
+                        }
+                        Err(e) => log::debug!("Runtime update not applied: {:?}", e),
+                    }
+                }
+            }
+            Err(e) => log::warn!("Could not subscribe to runtime updates: {}", e),
+        }
+        actix_web::rt::time::sleep(RESUBSCRIBE_DELAY).await;
+    }
+}
@@ -0,0 +1,54 @@
+use crate::state::RpcAPI;
+use futures::lock::Mutex;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex as SyncMutex};
+use subxt::utils::AccountId32;
+
+/// Pool rejections caused by a nonce already used or about to be used
+const NONCE_ERRORS: [&str; 3] = [
+    "Transaction is outdated",
+    "Priority is too low",
+    "Transaction Already Imported",
+];
+
+/// Hands out sequential nonces per signer so concurrent requests from the same
+/// account don't reuse the nonce subxt would look up on chain
+#[derive(Default)]
+pub struct NonceManager {
+    /// Next nonce of every account, locked on its own so accounts don't wait for each other
+    nonces: SyncMutex<HashMap<AccountId32, Arc<Mutex<Option<u64>>>>>,
+}
+
+impl NonceManager {
+    /// Next nonce of account, read from chain (including the pool) the first
+    /// time or after a resync
+    pub async fn next(&self, rpc: &RpcAPI, account: &AccountId32) -> Result<u64, subxt::Error> {
+        let entry = self.entry(account);
+        let mut next = entry.lock().await;
+        let nonce = match *next {
+            Some(nonce) => nonce,
+            None => rpc.system_account_next_index(account).await?,
+        };
+        *next = Some(nonce + 1);
+        Ok(nonce)
+    }
+
+    /// Forget the cached nonce, the next one is read from chain again
+    pub async fn resync(&self, account: &AccountId32) {
+        *self.entry(account).lock().await = None;
+    }
+
+    fn entry(&self, account: &AccountId32) -> Arc<Mutex<Option<u64>>> {
+        let mut nonces = self.nonces.lock().unwrap();
+        let entry = nonces.entry(account.clone()).or_default();
+        entry.clone()
+    }
+}
+
+/// Whether a submission error is a nonce collision that can be retried with a fresh nonce
+pub fn is_nonce_error(e: &subxt::Error) -> bool {
+    let message = e.to_string();
+    NONCE_ERRORS
+        .iter()
+        .any(|nonce_error| message.contains(nonce_error))
+}
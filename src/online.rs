@@ -1,12 +1,14 @@
 use crate::state::*;
 use crate::util::*;
 use actix_web::{error, web, HttpResponse};
-use futures::stream::StreamExt;
 use sugarfunge_api_types::online::*;
 use sugarfunge_api_types::sugarfunge;
 
-pub async fn get_authored_blocks(data: web::Data<AppState>) -> error::Result<HttpResponse> {
-    let api = &data.api;
+pub async fn get_authored_blocks(
+    data: web::Data<AppState>,
+    req: Option<web::Json<GetAuthoredBlocksInput>>,
+) -> error::Result<HttpResponse> {
+    let at = req.and_then(|req| req.into_inner().at);
     let result_array = Vec::new();
 
     let query_key = sugarfunge::storage()
@@ -14,31 +16,24 @@ pub async fn get_authored_blocks(data: web::Data<AppState>) -> error::Result<Htt
         .authored_blocks_iter()
         .to_root_bytes();
 
-    let storage = api.storage().at_latest().await.map_err(map_subxt_err)?;
+    let block = block_at(&data, &at).await?;
 
-    let keys_stream = storage
-        .fetch_raw_keys(query_key)
-        .await
-        .map_err(map_subxt_err)?;
-
-    let keys: Vec<Vec<u8>> = keys_stream
-        .collect::<Vec<_>>() // Collect into a Vec<Result<Vec<u8>, Error>>
-        .await // Await the collection process
-        .into_iter() // Convert into an iterator
-        .filter_map(Result::ok) // Filter out Ok values, ignore errors
-        .collect(); // Collect into a Vec<Vec<u8>>
+    let (keys, _) = fetch_keys_page(&data, &block, None, query_key, None, &None).await?;
 
     println!("Obtained keys:");
     for key in keys.iter() {
         println!("Key: len: {} 0x{}", key.len(), hex::encode(&key));
     }
-    Ok(HttpResponse::Ok().json(GetAuthoredBlocks {
+    Ok(ok_at(&block).json(GetAuthoredBlocks {
         validators: result_array,
     }))
 }
 
-pub async fn get_heartbeats(data: web::Data<AppState>) -> error::Result<HttpResponse> {
-    let api = &data.api;
+pub async fn get_heartbeats(
+    data: web::Data<AppState>,
+    req: Option<web::Json<GetHeartbeatsInput>>,
+) -> error::Result<HttpResponse> {
+    let at = req.and_then(|req| req.into_inner().at);
     let result_array = Vec::new();
 
     let query_key = sugarfunge::storage()
@@ -46,37 +41,30 @@ pub async fn get_heartbeats(data: web::Data<AppState>) -> error::Result<HttpResp
         .received_heartbeats_iter()
         .to_root_bytes();
 
-    let storage = api.storage().at_latest().await.map_err(map_subxt_err)?;
-
-    let keys_stream = storage
-        .fetch_raw_keys(query_key)
-        .await
-        .map_err(map_subxt_err)?;
+    let block = block_at(&data, &at).await?;
 
-    let keys: Vec<Vec<u8>> = keys_stream
-        .collect::<Vec<_>>() // Collect into a Vec<Result<Vec<u8>, Error>>
-        .await // Await the collection process
-        .into_iter() // Convert into an iterator
-        .filter_map(Result::ok) // Filter out Ok values, ignore errors
-        .collect(); // Collect into a Vec<Vec<u8>>
+    let (keys, _) = fetch_keys_page(&data, &block, None, query_key, None, &None).await?;
 
     // println!("Obtained keys:");
     for key in keys.iter() {
         println!("Key: len: {} 0x{}", key.len(), hex::encode(&key));
     }
-    Ok(HttpResponse::Ok().json(GetHeartbeats {
+    Ok(ok_at(&block).json(GetHeartbeats {
         validators: result_array,
     }))
 }
 
-pub async fn get_heartbeat_time(data: web::Data<AppState>) -> error::Result<HttpResponse> {
-    let api = &data.api;
-
+pub async fn get_heartbeat_time(
+    data: web::Data<AppState>,
+    req: Option<web::Json<HeartbeatTimeInput>>,
+) -> error::Result<HttpResponse> {
+    let at = req.and_then(|req| req.into_inner().at);
     let query_key = sugarfunge::storage().im_online().heartbeat_after();
 
-    let storage = api.storage().at_latest().await.map_err(map_subxt_err)?;
+    let block = block_at(&data, &at).await?;
+    let storage = block.storage();
 
     let keys_stream = storage.fetch(&query_key).await.map_err(map_subxt_err)?;
 
-    Ok(HttpResponse::Ok().json(HeartbeatTime { time: keys_stream }))
+    Ok(ok_at(&block).json(HeartbeatTime { time: keys_stream }))
 }
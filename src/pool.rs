@@ -1,14 +1,13 @@
-use crate::account;
 use crate::account::get_balance;
+use crate::auth::Caller;
+use crate::error::ApiError;
 use crate::state::*;
+use crate::tx::{respond, submit, TxOptions};
 use crate::util::*;
-use actix_web::{error, web, HttpResponse};
+use actix_web::{error, web, HttpRequest, HttpResponse};
 use codec::Decode;
-use futures::stream::StreamExt;
-use serde_json::json;
 use sp_core::sr25519::Public;
 use std::str::FromStr;
-use subxt::tx::PairSigner;
 use subxt::utils::AccountId32;
 use sugarfunge_api_types::pool::*;
 use sugarfunge_api_types::primitives::*;
@@ -20,10 +19,10 @@ use sugarfunge_api_types::sugarfunge::runtime_types::fula_pool::User as UserRunt
 
 pub async fn create_pool(
     data: web::Data<AppState>,
+    caller: Caller,
     req: web::Json<CreatePoolInput>,
 ) -> error::Result<HttpResponse> {
-    let pair = get_pair_from_seed(&req.seed)?;
-    let signer = PairSigner::new(pair);
+    let signer = get_signer(&data, &caller, &req.tx)?;
 
     let pool_name = String::from(&req.pool_name).into_bytes();
 
@@ -34,229 +33,177 @@ pub async fn create_pool(
     let peer_id = String::from(&req.peer_id).into_bytes();
     let peer_id = BoundedVec(peer_id);
 
-    let api = &data.api;
-
     let call = sugarfunge::tx().pool().create(pool_name, region, peer_id);
-    let set_balance = get_balance(&req.seed).await;
-    let result = api
-        .tx()
-        .sign_and_submit_then_watch(&call, &signer, Default::default())
-        .await
-        .map_err(map_subxt_err)?
-        .wait_for_finalized_success()
-        .await
-        .map_err(map_fula_pool_err)?;
-    let result = result
-        .find_first::<sugarfunge::pool::events::PoolCreated>()
-        .map_err(map_subxt_err)?;
-    if let Some(balance) = set_balance {
-        if let Err(value_error) = account::refund_fees(&req.seed.clone(), balance).await {
-            return Err(value_error);
+    let set_balance = get_balance(&data, signer.account_id()).await;
+    let options = TxOptions::new(&req.tx).refund_fees(signer.account_id(), set_balance);
+    respond(submit(&data, &call, &signer, options).await?, |receipt| {
+        let result = receipt
+            .events
+            .find_first::<sugarfunge::pool::events::PoolCreated>()
+            .map_err(map_subxt_err)?;
+        match result {
+            Some(event) => Ok(receipt.json(CreatePoolOutput {
+                owner: transform_option_account_value(event.owner).into(),
+                pool_id: event.pool_id.into(),
+            })),
+            None => {
+                let event = "sugarfunge::pool::events::PoolCreated";
+                Err(ApiError::EventNotFound(event.into()).into())
+            }
         }
-    }
-    match result {
-        Some(event) => Ok(HttpResponse::Ok().json(CreatePoolOutput {
-            owner: transform_option_account_value(event.owner).into(),
-            pool_id: event.pool_id.into(),
-        })),
-        None => Ok(HttpResponse::BadRequest().json(RequestError {
-            message: json!("Failed to find sugarfunge::pool::events::PoolCreated"),
-            description: format!(""),
-        })),
-    }
+    })
 }
 
 pub async fn leave_pool(
     data: web::Data<AppState>,
+    caller: Caller,
     req: web::Json<LeavePoolInput>,
 ) -> error::Result<HttpResponse> {
-    let pair = get_pair_from_seed(&req.seed)?;
-    let signer = PairSigner::new(pair);
+    let signer = get_signer(&data, &caller, &req.tx)?;
 
     let mut target_account: Option<AccountId32> = None::<AccountId32>;
     if let Some(value) = req.target_account.clone() {
         target_account = Some(AccountId32::try_from(&value).map_err(map_account_err)?);
     }
-    let api = &data.api;
-
     let call = sugarfunge::tx()
         .pool()
         .leave_pool(req.pool_id.into(), target_account);
-    let set_balance = get_balance(&req.seed).await;
-    let result = api
-        .tx()
-        .sign_and_submit_then_watch(&call, &signer, Default::default())
-        .await
-        .map_err(map_subxt_err)?
-        .wait_for_finalized_success()
-        .await
-        .map_err(map_fula_pool_err)?;
-    let result = result
-        .find_first::<sugarfunge::pool::events::ParticipantLeft>()
-        .map_err(map_subxt_err)?;
-    if let Some(balance) = set_balance {
-        if let Err(value_error) = account::refund_fees(&req.seed.clone(), balance).await {
-            return Err(value_error);
+    let set_balance = get_balance(&data, signer.account_id()).await;
+    let options = TxOptions::new(&req.tx).refund_fees(signer.account_id(), set_balance);
+    respond(submit(&data, &call, &signer, options).await?, |receipt| {
+        let result = receipt
+            .events
+            .find_first::<sugarfunge::pool::events::ParticipantLeft>()
+            .map_err(map_subxt_err)?;
+        match result {
+            Some(event) => Ok(receipt.json(LeavePoolOutput {
+                account: event.account.into(),
+                pool_id: event.pool_id.into(),
+            })),
+            None => {
+                let event = "sugarfunge::pool::events::ParticipantLeft";
+                Err(ApiError::EventNotFound(event.into()).into())
+            }
         }
-    }
-    match result {
-        Some(event) => Ok(HttpResponse::Ok().json(LeavePoolOutput {
-            account: event.account.into(),
-            pool_id: event.pool_id.into(),
-        })),
-        None => Ok(HttpResponse::BadRequest().json(RequestError {
-            message: json!("Failed to find sugarfunge::pool::events::ParticipantLeft"),
-            description: format!(""),
-        })),
-    }
+    })
 }
 
 pub async fn join_pool(
     data: web::Data<AppState>,
+    caller: Caller,
     req: web::Json<JoinPoolInput>,
 ) -> error::Result<HttpResponse> {
-    let pair = get_pair_from_seed(&req.seed)?;
-    let signer = PairSigner::new(pair);
+    let signer = get_signer(&data, &caller, &req.tx)?;
 
     let peer_id = String::from(&req.peer_id).into_bytes();
     let peer_id = BoundedVec(peer_id);
 
-    let api = &data.api;
-
     let call = sugarfunge::tx().pool().join(req.pool_id.into(), peer_id);
-    let set_balance = get_balance(&req.seed).await;
-    let result = api
-        .tx()
-        .sign_and_submit_then_watch(&call, &signer, Default::default())
-        .await
-        .map_err(map_subxt_err)?
-        .wait_for_finalized_success()
-        .await
-        .map_err(map_fula_pool_err)?;
-    let result = result
-        .find_first::<sugarfunge::pool::events::JoinRequested>()
-        .map_err(map_subxt_err)?;
-    if let Some(balance) = set_balance {
-        if let Err(value_error) = account::refund_fees(&req.seed.clone(), balance).await {
-            return Err(value_error);
+    let set_balance = get_balance(&data, signer.account_id()).await;
+    let options = TxOptions::new(&req.tx).refund_fees(signer.account_id(), set_balance);
+    respond(submit(&data, &call, &signer, options).await?, |receipt| {
+        let result = receipt
+            .events
+            .find_first::<sugarfunge::pool::events::JoinRequested>()
+            .map_err(map_subxt_err)?;
+        match result {
+            Some(event) => Ok(receipt.json(JoinPoolOutput {
+                account: event.account.into(),
+                pool_id: event.pool_id.into(),
+            })),
+            None => {
+                let event = "sugarfunge::pool::events::ParticipantLeft";
+                Err(ApiError::EventNotFound(event.into()).into())
+            }
         }
-    }
-    match result {
-        Some(event) => Ok(HttpResponse::Ok().json(JoinPoolOutput {
-            account: event.account.into(),
-            pool_id: event.pool_id.into(),
-        })),
-        None => Ok(HttpResponse::BadRequest().json(RequestError {
-            message: json!("Failed to find sugarfunge::pool::events::ParticipantLeft"),
-            description: format!(""),
-        })),
-    }
+    })
 }
 
 pub async fn cancel_join_pool(
     data: web::Data<AppState>,
+    caller: Caller,
     req: web::Json<CancelJoinPoolInput>,
 ) -> error::Result<HttpResponse> {
-    let pair = get_pair_from_seed(&req.seed)?;
-    let signer = PairSigner::new(pair);
+    let signer = get_signer(&data, &caller, &req.tx)?;
 
     let mut target_account: Option<AccountId32> = None::<AccountId32>;
     if let Some(value) = req.target_account.clone() {
         target_account = Some(AccountId32::try_from(&value).map_err(map_account_err)?);
     }
-    let api = &data.api;
-
     let call = sugarfunge::tx()
         .pool()
         .cancel_join(req.pool_id.into(), target_account);
-    let set_balance = get_balance(&req.seed).await;
-    let result = api
-        .tx()
-        .sign_and_submit_then_watch(&call, &signer, Default::default())
-        .await
-        .map_err(map_subxt_err)?
-        .wait_for_finalized_success()
-        .await
-        .map_err(map_fula_pool_err)?;
-    let result = result
-        .find_first::<sugarfunge::pool::events::RequestWithdrawn>()
-        .map_err(map_subxt_err)?;
-    if let Some(balance) = set_balance {
-        if let Err(value_error) = account::refund_fees(&req.seed.clone(), balance).await {
-            return Err(value_error);
+    let set_balance = get_balance(&data, signer.account_id()).await;
+    let options = TxOptions::new(&req.tx).refund_fees(signer.account_id(), set_balance);
+    respond(submit(&data, &call, &signer, options).await?, |receipt| {
+        let result = receipt
+            .events
+            .find_first::<sugarfunge::pool::events::RequestWithdrawn>()
+            .map_err(map_subxt_err)?;
+        match result {
+            Some(event) => Ok(receipt.json(CancelJoinPoolOutput {
+                account: event.account.into(),
+                pool_id: event.pool_id.into(),
+            })),
+            None => {
+                let event = "sugarfunge::pool::events::RequestWithdrawn";
+                Err(ApiError::EventNotFound(event.into()).into())
+            }
         }
-    }
-    match result {
-        Some(event) => Ok(HttpResponse::Ok().json(CancelJoinPoolOutput {
-            account: event.account.into(),
-            pool_id: event.pool_id.into(),
-        })),
-        None => Ok(HttpResponse::BadRequest().json(RequestError {
-            message: json!("Failed to find sugarfunge::pool::events::RequestWithdrawn"),
-            description: format!(""),
-        })),
-    }
+    })
 }
 
 pub async fn vote(
     data: web::Data<AppState>,
+    caller: Caller,
     req: web::Json<VoteInput>,
 ) -> error::Result<HttpResponse> {
-    let pair = get_pair_from_seed(&req.seed)?;
-    let signer = PairSigner::new(pair);
+    let signer = get_signer(&data, &caller, &req.tx)?;
 
     let account = AccountId32::try_from(&req.account).map_err(map_account_err)?;
 
     let peer_id = String::from(&req.peer_id).into_bytes();
     let peer_id = BoundedVec(peer_id);
 
-    let api = &data.api;
-
     let call = sugarfunge::tx()
         .pool()
         .vote(req.pool_id.into(), account, req.vote_value, peer_id);
-    let set_balance = get_balance(&req.seed).await;
-    let result = api
-        .tx()
-        .sign_and_submit_then_watch(&call, &signer, Default::default())
-        .await
-        .map_err(map_subxt_err)?
-        .wait_for_finalized_success()
-        .await
-        .map_err(map_fula_pool_err)?;
-    let result = result
-        .find_first::<sugarfunge::pool::events::VotingResult>()
-        .map_err(map_subxt_err)?;
-    if let Some(balance) = set_balance {
-        if let Err(value_error) = account::refund_fees(&req.seed.clone(), balance).await {
-            return Err(value_error);
+    let set_balance = get_balance(&data, signer.account_id()).await;
+    let options = TxOptions::new(&req.tx).refund_fees(signer.account_id(), set_balance);
+    respond(submit(&data, &call, &signer, options).await?, |receipt| {
+        let result = receipt
+            .events
+            .find_first::<sugarfunge::pool::events::VotingResult>()
+            .map_err(map_subxt_err)?;
+        match result {
+            Some(event) => Ok(receipt.json(VoteOutput {
+                account: event.account.into(),
+                pool_id: event.pool_id.into(),
+                result: String::from_utf8(event.result).unwrap_or_default().into(),
+            })),
+            None => {
+                let event = "sugarfunge::pool::events::Accepted";
+                Err(ApiError::EventNotFound(event.into()).into())
+            }
         }
-    }
-    match result {
-        Some(event) => Ok(HttpResponse::Ok().json(VoteOutput {
-            account: event.account.into(),
-            pool_id: event.pool_id.into(),
-            result: String::from_utf8(event.result).unwrap_or_default().into(),
-        })),
-        None => Ok(HttpResponse::BadRequest().json(RequestError {
-            message: json!("Failed to find sugarfunge::pool::events::Accepted"),
-            description: format!(""),
-        })),
-    }
+    })
 }
 
 pub async fn get_all_pools(
     data: web::Data<AppState>,
     req: web::Json<GetAllPoolInput>,
+    http: HttpRequest,
 ) -> error::Result<HttpResponse> {
-    let api = &data.api;
+    let cache = data.cache.scope(http.path());
     let mut result_array = Vec::new();
 
     let query_key: Vec<u8>;
     // println!("query_key manifests_root len: {}", query_key.len());
 
     if let Some(value) = req.region.clone() {
-        let key_value: u32 = value.parse().expect("Invalid number");
+        let key_value: u32 = value
+            .parse()
+            .map_err(|_| ApiError::InvalidInput(format!("Invalid region {}", value)))?;
         query_key = sugarfunge::storage()
             .pool()
             .pools(key_value)
@@ -266,69 +213,48 @@ pub async fn get_all_pools(
     }
     // println!("query_key pool_root len: {}", query_key.len());
 
-    let storage = api.storage().at_latest().await.map_err(map_subxt_err)?;
-
-    let keys_stream = storage
-        .fetch_raw_keys(query_key)
-        .await
-        .map_err(map_subxt_err)?;
-    let keys: Vec<Vec<u8>> = keys_stream
-        .collect::<Vec<_>>() // Collect into a Vec<Result<Vec<u8>, Error>>
-        .await // Await the collection process
-        .into_iter() // Convert into an iterator
-        .filter_map(Result::ok) // Filter out Ok values, ignore errors
-        .collect(); // Collect into a Vec<Vec<u8>>
-                    // println!("Obtained keys:");
-    for key in keys.iter() {
+    let block = block_at(&data, &req.at).await?;
+
+    let (entries, _) =
+        fetch_entries_page(&data, &block, cache.as_ref(), query_key, None, &None).await?;
+    let keys = key_decoder(&data, &sugarfunge::storage().pool().pools_iter())?;
+
+    // println!("Obtained keys:");
+    for (key, storage_data) in entries.iter() {
         let mut meet_requirements = true;
         // println!("Key: len: {} 0x{}", key.0.len(), hex::encode(&key));
 
-        let pool_id_idx = 48;
-        let pool_id_key = key.as_slice()[pool_id_idx..(pool_id_idx + 4)].to_vec();
-        let pool_id_id = u32::decode(&mut &pool_id_key[..]);
-        let pool_id = pool_id_id.unwrap();
+        let (pool_id,): (u32,) = keys.decode(key).map_err(map_storage_key_err)?;
         // println!("pool_id: {:?}", pool_id);
 
-        if let Some(storage_data) = storage
-            .fetch_raw(key.clone())
-            .await
-            .map_err(map_subxt_err)?
-        {
-            let value = PoolRuntime::decode(&mut &storage_data[..]);
-            let pool_value = value.unwrap();
+        let pool_value = PoolRuntime::decode(&mut &storage_data[..]).map_err(map_codec_err)?;
 
-            let storage = pool_value.participants.0;
+        let storage = pool_value.participants.0;
 
-            let mut storage_vec: Vec<Account> = Vec::new();
-
-            for storer in storage {
-                let current_account = Account::try_from(storer).unwrap();
-                storage_vec.push(current_account);
-            }
+        let storage_vec: Vec<Account> = storage.into_iter().map(Account::from).collect();
 
-            let pool_region = String::from_utf8(pool_value.region.0).unwrap_or_default();
+        let pool_region = String::from_utf8(pool_value.region.0).unwrap_or_default();
 
-            if let Some(region) = &req.region {
-                if *region != pool_region {
-                    meet_requirements = false;
-                }
+        if let Some(region) = &req.region {
+            if *region != pool_region {
+                meet_requirements = false;
             }
+        }
 
-            if meet_requirements {
-                result_array.push(PoolData {
-                    pool_id: pool_id.into(),
-                    pool_name: String::from_utf8(pool_value.name.0)
-                        .unwrap_or_default()
-                        .into(),
-                    region: pool_region,
-                    creator: transform_option_account_value(pool_value.owner),
-                    parent: transform_option_pool_value(pool_value.parent),
-                    participants: storage_vec,
-                });
-            }
+        if meet_requirements {
+            result_array.push(PoolData {
+                pool_id: pool_id.into(),
+                pool_name: String::from_utf8(pool_value.name.0)
+                    .unwrap_or_default()
+                    .into(),
+                region: pool_region,
+                creator: transform_option_account_value(pool_value.owner),
+                parent: transform_option_pool_value(pool_value.parent),
+                participants: storage_vec,
+            });
         }
     }
-    Ok(HttpResponse::Ok().json(GetAllPoolsOutput {
+    Ok(ok_cached(&block, &cache).json(GetAllPoolsOutput {
         pools: result_array,
     }))
 }
@@ -337,7 +263,6 @@ pub async fn get_all_pool_requests(
     data: web::Data<AppState>,
     req: web::Json<GetAllPoolRequestInput>,
 ) -> error::Result<HttpResponse> {
-    let api = &data.api;
     let mut result_array = Vec::new();
 
     // println!("query_key pool_root len: {}", query_key.len());
@@ -356,87 +281,61 @@ pub async fn get_all_pool_requests(
             .to_root_bytes();
     }
 
-    let storage = api.storage().at_latest().await.map_err(map_subxt_err)?;
+    let block = block_at(&data, &req.at).await?;
 
-    let keys_stream = storage
-        .fetch_raw_keys(query_key)
-        .await
-        .map_err(map_subxt_err)?;
+    let (entries, _) = fetch_entries_page(&data, &block, None, query_key, None, &None).await?;
 
-    let keys: Vec<Vec<u8>> = keys_stream
-        .collect::<Vec<_>>() // Collect into a Vec<Result<Vec<u8>, Error>>
-        .await // Await the collection process
-        .into_iter() // Convert into an iterator
-        .filter_map(Result::ok) // Filter out Ok values, ignore errors
-        .collect();
+    let keys = key_decoder(&data, &sugarfunge::storage().pool().pool_requests_iter())?;
 
     // println!("Obtained keys:");
-    for key in keys.iter() {
+    for (key, storage_data) in entries.iter() {
         let mut meet_requirements = true;
         // println!("Key: len: {} 0x{}", key.0.len(), hex::encode(&key));
 
-        let pool_id_idx = 48;
-        let pool_id_key = key.as_slice()[pool_id_idx..(pool_id_idx + 4)].to_vec();
-        let pool_id_id = u32::decode(&mut &pool_id_key[..]);
-        let pool_id = pool_id_id.unwrap();
+        let (pool_id, account_id): (u32, AccountId32) =
+            keys.decode(key).map_err(map_storage_key_err)?;
+        let account_id = Account::from(account_id);
         // println!("pool_id: {:?}", pool_id);
-
-        let account_idx = 68;
-        let account_key = key.as_slice()[account_idx..(account_idx + 32)].to_vec();
-        let account_id = AccountId32::decode(&mut &account_key[..]);
-        let account_id = Account::from(account_id.unwrap());
         // println!("account_id: {:?}", account_id);
 
-        if let Some(storage_data) = storage
-            .fetch_raw(key.clone())
-            .await
-            .map_err(map_subxt_err)?
-        {
-            let value = PoolRequestRuntime::decode(&mut &storage_data[..]);
-            let poolrequest_value = value.unwrap();
+        let poolrequest_value =
+            PoolRequestRuntime::decode(&mut &storage_data[..]).map_err(map_codec_err)?;
 
-            let voters = poolrequest_value.voted.0;
+        let voters = poolrequest_value.voted.0;
 
-            let mut voters_vec: Vec<Account> = Vec::new();
+        let voters_vec: Vec<Account> = voters.into_iter().map(Account::from).collect();
 
-            for voter in voters {
-                let current_account = Account::try_from(voter).unwrap();
-                voters_vec.push(current_account);
-            }
+        if let Some(account_filter) = req.account.clone() {
+            // Convert the account_id string to a Public key and then to a byte array
+            let account_public = Public::from_str(&account_id.as_str()).map_err(map_account_err)?;
+            let account_public_bytes: [u8; 32] = account_public.0;
+            let account_id_bytes = AccountId32::from(account_public_bytes);
 
-            if let Some(account_filter) = req.account.clone() {
-                // Convert the account_id string to a Public key and then to a byte array
-                let account_public =
-                    Public::from_str(&account_id.as_str()).map_err(map_account_err)?;
-                let account_public_bytes: [u8; 32] = account_public.0;
-                let account_id_bytes = AccountId32::from(account_public_bytes);
-
-                // Convert the account_filter string to a Public key and then to a byte array
-                let filter_public =
-                    Public::from_str(&account_filter.as_str()).map_err(map_account_err)?;
-                let filter_public_bytes: [u8; 32] = filter_public.0;
-                let account_filter_bytes = AccountId32::from(filter_public_bytes);
-
-                // Compare the account IDs
-                if account_id_bytes != account_filter_bytes {
-                    meet_requirements = false;
-                }
-            }
+            // Convert the account_filter string to a Public key and then to a byte array
+            let filter_public =
+                Public::from_str(&account_filter.as_str()).map_err(map_account_err)?;
+            let filter_public_bytes: [u8; 32] = filter_public.0;
+            let account_filter_bytes = AccountId32::from(filter_public_bytes);
 
-            if meet_requirements {
-                result_array.push(PoolRequestData {
-                    pool_id: pool_id.into(),
-                    account: account_id,
-                    voted: voters_vec,
-                    positive_votes: poolrequest_value.positive_votes,
-                    peer_id: String::from_utf8(poolrequest_value.peer_id.0)
-                        .unwrap_or_default()
-                        .into(),
-                });
+            // Compare the account IDs
+            if account_id_bytes != account_filter_bytes {
+                meet_requirements = false;
             }
         }
+
+        if meet_requirements {
+            result_array.push(PoolRequestData {
+                pool_id: pool_id.into(),
+                account: account_id,
+                voted: voters_vec,
+                positive_votes: poolrequest_value.positive_votes,
+                peer_id: String::from_utf8(poolrequest_value.peer_id.0)
+                    .unwrap_or_default()
+                    .into(),
+            });
+        }
     }
-    Ok(HttpResponse::Ok().json(GetAllPoolRequestsOutput {
+    Ok(ok_at(&block).json(GetAllPoolRequestsOutput {
         poolrequests: result_array,
     }))
 }
@@ -445,7 +344,6 @@ pub async fn get_all_pool_users(
     data: web::Data<AppState>,
     req: web::Json<GetAllPoolUsersInput>,
 ) -> error::Result<HttpResponse> {
-    let api = &data.api;
     let mut result_array = Vec::new();
 
     let query_key: Vec<u8>;
@@ -463,87 +361,66 @@ pub async fn get_all_pool_users(
         query_key = sugarfunge::storage().pool().users_iter().to_root_bytes();
     }
 
-    let storage = api.storage().at_latest().await.map_err(map_subxt_err)?;
-
-    let keys_stream = storage
-        .fetch_raw_keys(query_key)
-        .await
-        .map_err(map_subxt_err)?;
-
-    let keys: Vec<Vec<u8>> = keys_stream
-        .collect::<Vec<_>>() // Collect into a Vec<Result<Vec<u8>, Error>>
-        .await // Await the collection process
-        .into_iter() // Convert into an iterator
-        .filter_map(Result::ok) // Filter out Ok values, ignore errors
-        .collect(); // Collect into a Vec<Vec<u8>>
-
-    for key in keys.iter() {
-        let account_idx = 48;
-        let account_key = key.as_slice()[account_idx..(account_idx + 32)].to_vec();
-        let account_id = AccountId32::decode(&mut &account_key[..]);
-        let account_id = Account::from(account_id.unwrap());
-
-        if let Some(storage_data) = storage
-            .fetch_raw(key.clone())
-            .await
-            .map_err(map_subxt_err)?
-        {
-            let value = UserRuntime::<BoundedVec<u8>>::decode(&mut &storage_data[..]);
-            let user_value = value.unwrap();
-            let input_pool_id = req.pool_id.map(|id| id);
-            let input_request_pool_id = req.request_pool_id.map(|id| id);
-
-            let mut meet_requirements = true;
-
-            // Apply the filtering logic based on pool_id and request_pool_id
-            if input_pool_id.is_some() {
-                let input_pool_id_u32: u32 = input_pool_id.unwrap().into();
-                meet_requirements &= user_value.pool_id == Some(input_pool_id_u32);
-            }
-            if input_request_pool_id.is_some() {
-                let input_request_pool_id_u32: u32 = input_request_pool_id.unwrap().into();
-                meet_requirements &= user_value.request_pool_id == Some(input_request_pool_id_u32);
-            }
+    let block = block_at(&data, &req.at).await?;
 
-            if input_request_pool_id.is_some() && input_pool_id.is_some() {
-                let input_pool_id_u32: u32 = input_pool_id.unwrap().into();
-                let input_request_pool_id_u32: u32 = input_request_pool_id.unwrap().into();
-                meet_requirements = (user_value.request_pool_id == Some(input_request_pool_id_u32))
-                    || (user_value.pool_id == Some(input_pool_id_u32));
-            }
+    let (entries, next_cursor) =
+        fetch_entries_page(&data, &block, None, query_key, req.limit, &req.cursor).await?;
 
-            // Additional check for account value
-            // Additional check for account value
-            if let Some(account_value) = req.account.clone() {
-                // Convert the account_value string to a Public key and then to a byte array
-                let account_value_public =
-                    Public::from_str(&account_value.as_str()).map_err(map_account_err)?;
-                let account_value_public_bytes: [u8; 32] = account_value_public.0;
-                let account_value_id_bytes = AccountId32::from(account_value_public_bytes);
-
-                // Convert the account_id string to a Public key and then to a byte array
-                let account_id_public =
-                    Public::from_str(&account_id.as_str()).map_err(map_account_err)?;
-                let account_id_public_bytes: [u8; 32] = account_id_public.0;
-                let account_id_bytes = AccountId32::from(account_id_public_bytes);
-
-                // Compare the account IDs
-                meet_requirements &= account_value_id_bytes == account_id_bytes;
-            }
+    let keys = key_decoder(&data, &sugarfunge::storage().pool().users_iter())?;
+
+    for (key, storage_data) in entries.iter() {
+        let (account_id,): (AccountId32,) = keys.decode(key).map_err(map_storage_key_err)?;
+        let account_id = Account::from(account_id);
+
+        let user_value =
+            UserRuntime::<BoundedVec<u8>>::decode(&mut &storage_data[..]).map_err(map_codec_err)?;
+        let input_pool_id: Option<u32> = req.pool_id.map(|id| id.into());
+        let input_request_pool_id: Option<u32> = req.request_pool_id.map(|id| id.into());
 
-            if meet_requirements {
-                result_array.push(PoolUserData {
-                    account: account_id,
-                    pool_id: transform_option_pool_value(user_value.pool_id),
-                    request_pool_id: transform_option_pool_value(user_value.request_pool_id),
-                    peer_id: String::from_utf8(user_value.peer_id.0)
-                        .unwrap_or_default()
-                        .into(),
-                });
+        // Apply the filtering logic based on pool_id and request_pool_id, a user matching
+        // either of them is kept when both are given
+        let mut meet_requirements = match (input_pool_id, input_request_pool_id) {
+            (Some(pool_id), Some(request_pool_id)) => {
+                user_value.request_pool_id == Some(request_pool_id)
+                    || user_value.pool_id == Some(pool_id)
             }
+            (Some(pool_id), None) => user_value.pool_id == Some(pool_id),
+            (None, Some(request_pool_id)) => user_value.request_pool_id == Some(request_pool_id),
+            (None, None) => true,
+        };
+
+        // Additional check for account value
+        // Additional check for account value
+        if let Some(account_value) = req.account.clone() {
+            // Convert the account_value string to a Public key and then to a byte array
+            let account_value_public =
+                Public::from_str(&account_value.as_str()).map_err(map_account_err)?;
+            let account_value_public_bytes: [u8; 32] = account_value_public.0;
+            let account_value_id_bytes = AccountId32::from(account_value_public_bytes);
+
+            // Convert the account_id string to a Public key and then to a byte array
+            let account_id_public =
+                Public::from_str(&account_id.as_str()).map_err(map_account_err)?;
+            let account_id_public_bytes: [u8; 32] = account_id_public.0;
+            let account_id_bytes = AccountId32::from(account_id_public_bytes);
+
+            // Compare the account IDs
+            meet_requirements &= account_value_id_bytes == account_id_bytes;
+        }
+
+        if meet_requirements {
+            result_array.push(PoolUserData {
+                account: account_id,
+                pool_id: transform_option_pool_value(user_value.pool_id),
+                request_pool_id: transform_option_pool_value(user_value.request_pool_id),
+                peer_id: String::from_utf8(user_value.peer_id.0)
+                    .unwrap_or_default()
+                    .into(),
+            });
         }
     }
-    Ok(HttpResponse::Ok().json(GetAllPoolUsersOutput {
+    Ok(ok_at(&block).json(GetAllPoolUsersOutput {
         users: result_array,
+        next_cursor,
     }))
 }
@@ -0,0 +1,167 @@
+use crate::auth::Caller;
+use crate::error::ApiError;
+use crate::state::*;
+use crate::tx::{respond, submit, TxEvents, TxOptions};
+use crate::util::*;
+use actix_web::{error, web, HttpResponse};
+use scale_info::TypeDef;
+use subxt::dynamic::Value;
+use subxt::error::DispatchError;
+use subxt::tx::{DynamicPayload, TxPayload};
+use subxt::utils::AccountId32;
+use sugarfunge_api_types::primitives::*;
+use sugarfunge_api_types::proxy::*;
+
+const PROXY_PALLET: &str = "Proxy";
+
+const DEFAULT_PROXY_TYPE: &str = "Any";
+
+/// Registers or revokes a delegate allowed to submit calls for the signer
+pub async fn proxy(
+    data: web::Data<AppState>,
+    caller: Caller,
+    req: web::Json<ProxyInput>,
+) -> error::Result<HttpResponse> {
+    let signer = get_signer(&data, &caller, &req.tx)?;
+    let delegate = AccountId32::try_from(&req.delegate).map_err(map_account_err)?;
+    let proxy_type = req.proxy_type.as_deref().unwrap_or(DEFAULT_PROXY_TYPE);
+    let delay = req.delay.unwrap_or_default();
+    let call_name = if req.revoke {
+        "remove_proxy"
+    } else {
+        "add_proxy"
+    };
+    let fields = proxy_call_fields(&data, call_name)?;
+    let call = subxt::dynamic::tx(
+        PROXY_PALLET,
+        call_name,
+        vec![
+            lookup_value(&data, fields[0], &delegate),
+            proxy_type_value(&data, fields[1], proxy_type)?,
+            Value::u128(delay.into()),
+        ],
+    );
+
+    let options = TxOptions::new(&req.tx);
+    respond(submit(&data, &call, &signer, options).await?, |receipt| {
+        let variant = if req.revoke {
+            "ProxyRemoved"
+        } else {
+            "ProxyAdded"
+        };
+        let mut found = false;
+        for event in receipt.events.iter() {
+            let event = event.map_err(map_subxt_err)?;
+            found |= event.pallet_name() == PROXY_PALLET && event.variant_name() == variant;
+        }
+        if !found {
+            return Err(ApiError::EventNotFound(format!("{}::{}", PROXY_PALLET, variant)).into());
+        }
+        let account = match &req.tx.on_behalf_of {
+            Some(real) => real.clone(),
+            None => signer.account_id().clone().into(),
+        };
+        Ok(receipt.json(ProxyOutput {
+            account,
+            delegate: req.delegate.clone(),
+            proxy_type: proxy_type.to_string(),
+            delay,
+            revoked: req.revoke,
+        }))
+    })
+}
+
+/// Wraps a call in `Proxy::proxy`, so the signer dispatches it for the `real` account that made
+/// the signer its delegate
+pub fn proxy_call<Call: TxPayload>(
+    data: &AppState,
+    call: &Call,
+    real: &Account,
+) -> error::Result<DynamicPayload> {
+    let real = AccountId32::try_from(real).map_err(map_account_err)?;
+    let fields = proxy_call_fields(data, "proxy")?;
+    let call_data = data.api.tx().call_data(call).map_err(map_subxt_err)?;
+    Ok(subxt::dynamic::tx(
+        PROXY_PALLET,
+        "proxy",
+        vec![
+            lookup_value(data, fields[0], &real),
+            Value::unnamed_variant("None", []),
+            call_value(data, &call_data)?,
+        ],
+    ))
+}
+
+/// Dispatch result of the call of a `Proxy::proxy` extrinsic. The extrinsic succeeds even when
+/// the wrapped call fails, its error is in `ProxyExecuted`.
+pub fn proxied_result(data: &AppState, events: &TxEvents) -> Result<(), ApiError> {
+    for event in events.iter() {
+        let event = event.map_err(|e| ApiError::Decoding(e.to_string()))?;
+        if event.pallet_name() != PROXY_PALLET || event.variant_name() != "ProxyExecuted" {
+            continue;
+        }
+        if let Some((&1, error)) = event.field_bytes().split_first() {
+            return Err(
+                match DispatchError::decode_from(error, data.api.metadata()) {
+                    Ok(e) => ApiError::from(subxt::Error::Runtime(e)),
+                    Err(e) => ApiError::Decoding(e.to_string()),
+                },
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Type ids of the fields of a call of the Proxy pallet in the live runtime
+fn proxy_call_fields(data: &AppState, call: &str) -> error::Result<Vec<u32>> {
+    let metadata = data.api.metadata();
+    let pallet = metadata.pallet_by_name(PROXY_PALLET).ok_or_else(|| {
+        ApiError::IncompatibleRuntime(
+            "The runtime has no Proxy pallet, delegated signing is not supported".into(),
+        )
+    })?;
+    let call = pallet.call_variant_by_name(call).ok_or_else(|| {
+        ApiError::IncompatibleRuntime(format!("The runtime has no Proxy::{} call", call))
+    })?;
+    // add_proxy, remove_proxy and proxy all take three fields
+    if call.fields.len() != 3 {
+        return Err(ApiError::IncompatibleRuntime(format!(
+            "Proxy::{} of the runtime has unexpected fields",
+            call.name
+        ))
+        .into());
+    }
+    Ok(call.fields.iter().map(|field| field.ty.id).collect())
+}
+
+/// Account as the lookup source of type `ty`, a `MultiAddress` in most runtimes or the account
+/// itself
+fn lookup_value(data: &AppState, ty: u32, account: &AccountId32) -> Value {
+    let metadata = data.api.metadata();
+    match metadata.types().resolve(ty).map(|ty| &ty.type_def) {
+        Some(TypeDef::Variant(_)) => Value::unnamed_variant("Id", [account_value(account)]),
+        _ => account_value(account),
+    }
+}
+
+/// Variant `name` of the proxy type of the runtime, whose variants differ between runtimes
+fn proxy_type_value(data: &AppState, ty: u32, name: &str) -> error::Result<Value> {
+    let metadata = data.api.metadata();
+    let variants = match metadata.types().resolve(ty).map(|ty| &ty.type_def) {
+        Some(TypeDef::Variant(variant)) => &variant.variants,
+        _ => return Err(ApiError::Decoding("Proxy type is not an enum".into()).into()),
+    };
+    if !variants.iter().any(|variant| variant.name == name) {
+        let names: Vec<&str> = variants
+            .iter()
+            .map(|variant| variant.name.as_str())
+            .collect();
+        return Err(ApiError::InvalidInput(format!(
+            "Unknown proxy type {}, expected one of {}",
+            name,
+            names.join(", ")
+        ))
+        .into());
+    }
+    Ok(Value::unnamed_variant(name, []))
+}
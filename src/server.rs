@@ -0,0 +1,390 @@
+use crate::args::Args;
+use actix_cors::Cors;
+use actix_web::http::{header::HeaderName, Method};
+use dotenv::dotenv;
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::fs::File;
+use std::io::{BufReader, Error, ErrorKind};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use url::Url;
+
+/// Response headers the api sets, readable by browser clients
+const EXPOSED_HEADERS: [&str; 5] = [
+    "X-Tx-Confirmation",
+    "X-Tx-Block-Hash",
+    "X-Block-Hash",
+    "X-Block-Number",
+    "X-Cache",
+];
+
+/// Listen address, TLS, body limit and CORS policy of the http server
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct ServerConfig {
+    pub listen: Url,
+    pub tls: Option<TlsConfig>,
+    /// Most bytes of a request body
+    pub body_limit: usize,
+    pub cors: CorsConfig,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct TlsConfig {
+    /// PEM certificate chain
+    pub cert: PathBuf,
+    /// PEM private key, PKCS#8 or RSA
+    pub key: PathBuf,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct CorsConfig {
+    /// Allowed origins, `*` allows any origin, a trailing `*` any port of the host and a leading
+    /// `*.` host label any subdomain
+    pub origins: Vec<String>,
+    pub methods: Vec<String>,
+    pub headers: Vec<String>,
+    /// Allow cookies and credentials, not compatible with the `*` origin
+    pub credentials: bool,
+    /// Seconds browsers may cache a preflight response
+    pub max_age: usize,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            listen: Url::parse("http://127.0.0.1:4000").unwrap(),
+            tls: None,
+            body_limit: 2 * 1024 * 1024,
+            cors: CorsConfig::default(),
+        }
+    }
+}
+
+impl Default for CorsConfig {
+    fn default() -> Self {
+        Self {
+            origins: vec!["http://localhost*".into()],
+            methods: vec!["GET".into(), "POST".into()],
+            headers: vec![
+                "Authorization".into(),
+                "Accept".into(),
+                "Content-Type".into(),
+                "X-API-Key".into(),
+            ],
+            credentials: false,
+            max_age: 3600,
+        }
+    }
+}
+
+impl ServerConfig {
+    /// Load the server config from a json file, the environment variables and then the command
+    /// line arguments override it
+    pub fn load(args: &Args) -> std::io::Result<Self> {
+        dotenv().ok();
+        let mut config: ServerConfig = match &args.server_config {
+            Some(path) => serde_json::from_slice(&std::fs::read(path)?)
+                .map_err(|e| Error::new(ErrorKind::InvalidData, e))?,
+            None => ServerConfig::default(),
+        };
+
+        if let Ok(listen) = env::var("LISTEN") {
+            config.listen = parse_env("LISTEN", &listen)?;
+        }
+        if let (Ok(cert), Ok(key)) = (env::var("TLS_CERT"), env::var("TLS_KEY")) {
+            config.tls = Some(TlsConfig {
+                cert: cert.into(),
+                key: key.into(),
+            });
+        }
+        if let Ok(body_limit) = env::var("BODY_LIMIT") {
+            config.body_limit = parse_env("BODY_LIMIT", &body_limit)?;
+        }
+        if let Ok(origins) = env::var("CORS_ORIGINS") {
+            config.cors.origins = split_list(&origins);
+        }
+        if let Ok(methods) = env::var("CORS_METHODS") {
+            config.cors.methods = split_list(&methods);
+        }
+        if let Ok(headers) = env::var("CORS_HEADERS") {
+            config.cors.headers = split_list(&headers);
+        }
+        if let Ok(credentials) = env::var("CORS_CREDENTIALS") {
+            config.cors.credentials = parse_env("CORS_CREDENTIALS", &credentials)?;
+        }
+        if let Ok(max_age) = env::var("CORS_MAX_AGE") {
+            config.cors.max_age = parse_env("CORS_MAX_AGE", &max_age)?;
+        }
+
+        if let Some(listen) = &args.listen {
+            config.listen = listen.clone();
+        }
+        if let (Some(cert), Some(key)) = (&args.tls_cert, &args.tls_key) {
+            config.tls = Some(TlsConfig {
+                cert: cert.clone(),
+                key: key.clone(),
+            });
+        }
+        if let Some(body_limit) = args.body_limit {
+            config.body_limit = body_limit;
+        }
+        if !args.cors_origins.is_empty() {
+            config.cors.origins = args.cors_origins.clone();
+        }
+        if !args.cors_methods.is_empty() {
+            config.cors.methods = args.cors_methods.clone();
+        }
+        if !args.cors_headers.is_empty() {
+            config.cors.headers = args.cors_headers.clone();
+        }
+        if args.cors_credentials {
+            config.cors.credentials = true;
+        }
+        if let Some(max_age) = args.cors_max_age {
+            config.cors.max_age = max_age;
+        }
+
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Host and port to bind
+    pub fn address(&self) -> std::io::Result<(String, u16)> {
+        match (self.listen.host_str(), self.listen.port_or_known_default()) {
+            (Some(host), Some(port)) => Ok((host.to_string(), port)),
+            _ => Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("Invalid listen address {}", self.listen),
+            )),
+        }
+    }
+
+    /// Rustls config of the certificate and key, when TLS is configured
+    pub fn rustls(&self) -> std::io::Result<Option<rustls::ServerConfig>> {
+        let tls = match &self.tls {
+            Some(tls) => tls,
+            None => return Ok(None),
+        };
+        let certs = rustls_pemfile::certs(&mut BufReader::new(File::open(&tls.cert)?))?
+            .into_iter()
+            .map(rustls::Certificate)
+            .collect();
+        let key = private_key(&tls.key)?;
+        rustls::ServerConfig::builder()
+            .with_safe_defaults()
+            .with_no_client_auth()
+            .with_single_cert(certs, key)
+            .map(Some)
+            .map_err(|e| Error::new(ErrorKind::InvalidData, e))
+    }
+
+    /// Checks the parts actix would otherwise only reject at the first request
+    fn validate(&self) -> std::io::Result<()> {
+        self.address()?;
+        if self.listen.scheme() == "https" && self.tls.is_none() {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Listening on https needs a TLS certificate and key",
+            ));
+        }
+        // Browsers send the origin as `scheme://host[:port]`, anything else would never match
+        for origin in self.cors.origins.iter() {
+            if origin != "*" && OriginPattern::parse(origin).is_none() {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("Invalid CORS origin {}", origin),
+                ));
+            }
+        }
+        for method in self.cors.methods.iter() {
+            Method::from_str(method).map_err(|_| {
+                Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("Invalid CORS method {}", method),
+                )
+            })?;
+        }
+        for header in self.cors.headers.iter() {
+            HeaderName::from_str(header).map_err(|_| {
+                Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("Invalid CORS header {}", header),
+                )
+            })?;
+        }
+        if self.cors.credentials && self.cors.origins.iter().any(|origin| origin == "*") {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "CORS credentials can't be allowed for any origin",
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl CorsConfig {
+    pub fn cors(&self) -> Cors {
+        let mut cors = Cors::default()
+            .allowed_methods(self.methods.iter().map(|method| method.as_str()))
+            .allowed_headers(self.headers.iter().map(|header| header.as_str()))
+            .expose_headers(EXPOSED_HEADERS)
+            .max_age(self.max_age);
+        for origin in self.origins.iter() {
+            cors = match origin.as_str() {
+                "*" => cors.allow_any_origin(),
+                origin if origin.contains('*') => match OriginPattern::parse(origin) {
+                    Some(pattern) => cors.allowed_origin_fn(move |origin, _req_head| {
+                        origin
+                            .to_str()
+                            .map_or(false, |origin| pattern.matches(origin))
+                    }),
+                    None => cors,
+                },
+                origin => cors.allowed_origin(origin),
+            };
+        }
+        if self.credentials {
+            cors = cors.supports_credentials();
+        }
+        cors
+    }
+}
+
+/// Allowed origin, `scheme://host*` matches any port of the host and `scheme://*.domain` any
+/// subdomain of the domain, without matching the domain itself
+#[derive(Debug, PartialEq)]
+struct OriginPattern {
+    scheme: String,
+    host: String,
+    subdomains: bool,
+    any_port: bool,
+    port: Option<u16>,
+}
+
+impl OriginPattern {
+    /// Wildcards are only allowed before the port and as the first host label, so a pattern never
+    /// matches a host of another domain
+    fn parse(pattern: &str) -> Option<Self> {
+        let (pattern, any_port) = match pattern.strip_suffix('*') {
+            Some(pattern) => (pattern, true),
+            None => (pattern, false),
+        };
+        let (scheme, host) = pattern.split_once("://")?;
+        let (host, subdomains) = match host.strip_prefix("*.") {
+            Some(host) => (host, true),
+            None => (host, false),
+        };
+        let origin = format!("{}://{}", scheme, host);
+        let url = Url::parse(&origin).ok()?;
+        let invalid_host = host.contains('*') || host.ends_with('.');
+        if invalid_host || url.origin().ascii_serialization() != origin {
+            return None;
+        }
+        if (any_port && url.port().is_some()) || (subdomains && url.domain().is_none()) {
+            return None;
+        }
+        Some(Self {
+            scheme: url.scheme().to_string(),
+            host: url.host_str()?.to_string(),
+            subdomains,
+            any_port,
+            port: url.port(),
+        })
+    }
+
+    fn matches(&self, origin: &str) -> bool {
+        let url = match Url::parse(origin) {
+            Ok(url) if url.origin().ascii_serialization() == origin => url,
+            _ => return false,
+        };
+        let host = match url.host_str() {
+            Some(host) => host,
+            None => return false,
+        };
+        let host_matches = match self.subdomains {
+            true => host
+                .strip_suffix(self.host.as_str())
+                .and_then(|subdomain| subdomain.strip_suffix('.'))
+                .map_or(false, |subdomain| !subdomain.is_empty()),
+            false => host == self.host,
+        };
+        url.scheme() == self.scheme && host_matches && (self.any_port || url.port() == self.port)
+    }
+}
+
+fn private_key(path: &Path) -> std::io::Result<rustls::PrivateKey> {
+    let mut reader = BufReader::new(File::open(path)?);
+    loop {
+        match rustls_pemfile::read_one(&mut reader)? {
+            Some(rustls_pemfile::Item::PKCS8Key(key))
+            | Some(rustls_pemfile::Item::RSAKey(key))
+            | Some(rustls_pemfile::Item::ECKey(key)) => return Ok(rustls::PrivateKey(key)),
+            Some(_) => continue,
+            None => {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    format!("No private key in {}", path.display()),
+                ))
+            }
+        }
+    }
+}
+
+fn parse_env<T: FromStr>(name: &str, value: &str) -> std::io::Result<T> {
+    value.parse().map_err(|_| {
+        Error::new(
+            ErrorKind::InvalidInput,
+            format!("Invalid {} {}", name, value),
+        )
+    })
+}
+
+fn split_list(list: &str) -> Vec<String> {
+    list.split(',')
+        .map(|item| item.trim().to_string())
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn port_wildcards_match_only_the_port() {
+        let pattern = OriginPattern::parse("http://localhost*").unwrap();
+        assert!(pattern.matches("http://localhost"));
+        assert!(pattern.matches("http://localhost:3000"));
+        assert!(!pattern.matches("http://localhost.evil.com"));
+        assert!(!pattern.matches("http://localhost.evil.com:3000"));
+        assert!(!pattern.matches("https://localhost:3000"));
+
+        let pattern = OriginPattern::parse("https://app.example.com*").unwrap();
+        assert!(pattern.matches("https://app.example.com:8443"));
+        assert!(!pattern.matches("https://app.example.com.attacker.net"));
+    }
+
+    #[test]
+    fn subdomain_wildcards_match_whole_labels() {
+        let pattern = OriginPattern::parse("https://*.example.com").unwrap();
+        assert!(pattern.matches("https://app.example.com"));
+        assert!(pattern.matches("https://a.b.example.com"));
+        assert!(!pattern.matches("https://example.com"));
+        assert!(!pattern.matches("https://attackerexample.com"));
+        assert!(!pattern.matches("https://app.example.com.attacker.net"));
+        assert!(!pattern.matches("https://app.example.com:8443"));
+    }
+
+    #[test]
+    fn rejects_other_wildcards() {
+        assert_eq!(OriginPattern::parse("https://app.example.*"), None);
+        assert_eq!(OriginPattern::parse("https://app*.example.com"), None);
+        assert_eq!(OriginPattern::parse("https://*example.com"), None);
+        assert_eq!(OriginPattern::parse("http://localhost:3000*"), None);
+        assert_eq!(OriginPattern::parse("https://*.127.0.0.1"), None);
+        assert_eq!(OriginPattern::parse("https://app.example.com/*"), None);
+    }
+}
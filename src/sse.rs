@@ -0,0 +1,249 @@
+use crate::error::ApiError;
+use crate::events::*;
+use crate::state::*;
+use actix_web::http::header::{CacheControl, CacheDirective, ContentEncoding, HeaderValue};
+use actix_web::rt::time::timeout;
+use actix_web::web::Bytes;
+use actix_web::{error, web, HttpRequest, HttpResponse};
+use futures::channel::mpsc;
+use futures::{SinkExt, StreamExt};
+use std::time::Duration;
+use sugarfunge_api_types::subscription::*;
+
+/// Interval of the comments keeping idle streams open through proxies
+const KEEP_ALIVE: Duration = Duration::from_secs(15);
+
+/// Chunks queued for a client before the stream waits for it
+const STREAM_QUEUE: usize = 16;
+
+/// Milliseconds event sources wait before reconnecting to a closed stream
+const RETRY_MS: u64 = 3000;
+
+const LAST_EVENT_ID: &str = "Last-Event-ID";
+
+/// Where a stream resumes, the events of `block` up to index `after` were already received
+struct Resume {
+    block: u32,
+    after: Option<u32>,
+}
+
+/// Streams the events of finalized blocks as server-sent events, with the topics and filter of
+/// the `/ws` subscriptions
+pub async fn events(
+    data: web::Data<AppState>,
+    req: HttpRequest,
+    query: web::Query<EventStreamInput>,
+) -> error::Result<HttpResponse> {
+    let filter = EventFilter {
+        account: query.account.clone(),
+        class_id: query.class_id,
+        asset_id: query.asset_id,
+        pool_id: query.pool_id,
+        cid: query.cid.clone(),
+    };
+    let filter = Filter::new(&topics(&query.topics), &filter).map_err(ApiError::InvalidInput)?;
+    let resume = match req.headers().get(LAST_EVENT_ID) {
+        Some(id) => Some(last_event_id(id)?),
+        None => query.from_block.map(|block| Resume { block, after: None }),
+    };
+
+    let (tx, rx) = mpsc::channel(STREAM_QUEUE);
+    actix_web::rt::spawn(stream_events(data, filter, resume, tx));
+    Ok(HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .insert_header(CacheControl(vec![CacheDirective::NoCache]))
+        // Compression would hold events back until its buffer fills
+        .insert_header(ContentEncoding::Identity)
+        .streaming(rx.map(Ok::<_, error::Error>)))
+}
+
+async fn stream_events(
+    data: web::Data<AppState>,
+    filter: Filter,
+    resume: Option<Resume>,
+    mut tx: mpsc::Sender<Bytes>,
+) {
+    let retry = format!("retry: {}\n\n", RETRY_MS);
+    if send(&mut tx, retry.into()).await.is_err() {
+        return;
+    }
+    if let Err(message) = follow(&data, &filter, resume, &mut tx).await {
+        let data = serde_json::json!({ "message": message });
+        let _ = tx
+            .send(format!("event: error\ndata: {}\n\n", data).into())
+            .await;
+    }
+}
+
+/// Sends the replayed events and then the live ones, until the client is gone or an error ends
+/// the stream
+async fn follow(
+    data: &AppState,
+    filter: &Filter,
+    resume: Option<Resume>,
+    tx: &mut mpsc::Sender<Bytes>,
+) -> Result<(), String> {
+    let mut next = match resume {
+        Some(resume) => {
+            let range = replay_range(data, resume.block).await?;
+            let next = range.end() + 1;
+            for number in range {
+                let block = historical_block(data, number).await?;
+                let after = resume.after.filter(|_| number == resume.block);
+                send_block(tx, filter, &block, after).await?;
+            }
+            Some(next)
+        }
+        None => None,
+    };
+
+    let (bus, mut blocks) = mpsc::channel(SESSION_QUEUE);
+    data.events.do_send(Connect(bus));
+    loop {
+        let block = match timeout(KEEP_ALIVE, blocks.next()).await {
+            Ok(Some(block)) => block,
+            Ok(None) => return Err("Events were not read fast enough, reconnect to resume".into()),
+            Err(_) => {
+                send(tx, Bytes::from_static(b": keep-alive\n\n")).await?;
+                continue;
+            }
+        };
+        if let Some(number) = next {
+            if block.number < number {
+                continue;
+            }
+            // Blocks finalized between the end of the replay and the first live block
+            for number in number..block.number {
+                send_block(tx, filter, &historical_block(data, number).await?, None).await?;
+            }
+        }
+        send_block(tx, filter, &block, None).await?;
+        next = Some(block.number + 1);
+    }
+}
+
+/// Sends the matching events of a block after index `after`, each with its id. The id of the
+/// last event of the block follows when it didn't match, so the id kept by the client moves
+/// forward with every block and not only with the events it gets.
+async fn send_block(
+    tx: &mut mpsc::Sender<Bytes>,
+    filter: &Filter,
+    block: &BlockEvents,
+    after: Option<u32>,
+) -> Result<(), String> {
+    let unsent = |event: &&BlockEvent| after.map_or(true, |after| event.index > after);
+    let mut last_sent = None;
+    for event in block
+        .events
+        .iter()
+        .filter(unsent)
+        .filter(|event| filter.matches(event))
+    {
+        let data = StreamEvent {
+            block_number: block.number,
+            block_hash: format!("{:?}", block.hash),
+            index: event.index,
+            event: event.event.clone(),
+        };
+        let data = serde_json::to_string(&data).map_err(|e| e.to_string())?;
+        let chunk = format!("id: {}:{}\ndata: {}\n\n", block.number, event.index, data);
+        send(tx, chunk.into()).await?;
+        last_sent = Some(event.index);
+    }
+    // An event with only an id is not dispatched but still sets the last event id
+    if let Some(last) = block.events.last().filter(unsent) {
+        if last_sent != Some(last.index) {
+            send(
+                tx,
+                format!("id: {}:{}\n\n", block.number, last.index).into(),
+            )
+            .await?;
+        }
+    }
+    Ok(())
+}
+
+async fn send(tx: &mut mpsc::Sender<Bytes>, chunk: Bytes) -> Result<(), String> {
+    tx.send(chunk)
+        .await
+        .map_err(|_| "The client closed the stream".to_string())
+}
+
+/// Topics given as `Pallet` or `Pallet.Event`, separated by commas
+fn topics(topics: &str) -> Vec<EventTopic> {
+    topics
+        .split(',')
+        .map(str::trim)
+        .filter(|topic| !topic.is_empty())
+        .map(|topic| match topic.split_once('.') {
+            Some((pallet, event)) => EventTopic {
+                pallet: pallet.to_string(),
+                events: vec![event.to_string()],
+            },
+            None => EventTopic {
+                pallet: topic.to_string(),
+                events: vec![],
+            },
+        })
+        .collect()
+}
+
+/// Id of the last event a client received, `<block_number>:<index>`
+fn last_event_id(id: &HeaderValue) -> Result<Resume, ApiError> {
+    id.to_str()
+        .ok()
+        .and_then(|id| id.split_once(':'))
+        .and_then(|(block, index)| {
+            Some(Resume {
+                block: block.parse().ok()?,
+                after: Some(index.parse().ok()?),
+            })
+        })
+        .ok_or_else(|| {
+            ApiError::InvalidInput(format!(
+                "Invalid {}, expected <block_number>:<index>",
+                LAST_EVENT_ID
+            ))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_topics() {
+        let parsed = topics(" Asset.Mint, Bag ,,Fula.ManifestOutput");
+        let parsed: Vec<_> = parsed
+            .iter()
+            .map(|topic| (topic.pallet.as_str(), topic.events.clone()))
+            .collect();
+        assert_eq!(
+            parsed,
+            vec![
+                ("Asset", vec!["Mint".to_string()]),
+                ("Bag", vec![]),
+                ("Fula", vec!["ManifestOutput".to_string()]),
+            ]
+        );
+        assert!(topics("").is_empty());
+    }
+
+    #[test]
+    fn parses_last_event_id() {
+        let resume = last_event_id(&HeaderValue::from_static("104234:7")).unwrap();
+        assert_eq!((resume.block, resume.after), (104234, Some(7)));
+    }
+
+    #[test]
+    fn rejects_invalid_last_event_ids() {
+        for id in ["", "104234", "104234:", ":7", "block:7", "104234:-1"] {
+            let result = last_event_id(&HeaderValue::from_static(id));
+            assert!(
+                matches!(result, Err(ApiError::InvalidInput(_))),
+                "id {}",
+                id
+            );
+        }
+    }
+}
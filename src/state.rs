@@ -1,3 +1,12 @@
+use crate::cache::Cache;
+use crate::compat::Compat;
+use crate::events::EventBus;
+use crate::keystore::Keystore;
+use crate::node::NodeClient;
+use crate::nonce::NonceManager;
+use crate::tx::{PayloadTracker, TxTracker};
+use crate::webhook::Webhooks;
+use actix::Addr;
 use std::sync::Arc;
 use subxt::{client::OnlineClient, PolkadotConfig, backend::legacy::LegacyRpcMethods};
 
@@ -8,6 +17,15 @@ pub type RpcAPI = Arc<LegacyRpcMethods<PolkadotConfig>>;
 #[derive(Clone)]
 pub struct AppState {
     pub api: ClientAPI,
-    pub node_url: String,  // Add this to hold the node URL
+    pub node: NodeClient, // Active node endpoint and its connection state
     pub rpc: RpcAPI,
+    pub keystore: Arc<Keystore>,
+    pub allow_seed: bool, // Accept raw seeds in request bodies
+    pub txs: Arc<TxTracker>,
+    pub payloads: Arc<PayloadTracker>, // Unsigned payloads waiting for an external signature
+    pub nonces: Arc<NonceManager>,
+    pub cache: Arc<Cache>,
+    pub compat: Arc<Compat>,
+    pub events: Addr<EventBus>, // Finalized events shared by the subscription sessions
+    pub webhooks: Arc<Webhooks>, // Client posting async transaction status changes
 }
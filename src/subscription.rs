@@ -1,11 +1,15 @@
+use crate::events::*;
 use crate::state::*;
 use actix::prelude::*;
 use actix_web::{web, Error, HttpRequest, HttpResponse};
 use actix_web_actors::ws;
-use crossbeam::channel;
+use futures::channel::mpsc;
+use futures::StreamExt;
 use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
 use std::time::{Duration, Instant};
-use sugarfunge_api_types::sugarfunge;
+use sugarfunge_api_types::subscription::*;
 
 /// How often heartbeat pings are sent
 const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(5);
@@ -13,183 +17,147 @@ const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(5);
 /// How long before lack of client response causes a timeout
 const CLIENT_TIMEOUT: Duration = Duration::from_secs(10);
 
+/// Bytes written for a client and not yet taken by the connection before its session is closed
+const MAX_UNSENT_BYTES: usize = 4 * 1024 * 1024;
+
 /// websocket connection is long running connection
 pub struct SubcriptionServiceWS {
     data: web::Data<AppState>,
     /// Client must send ping at least once per CLIENT_TIMEOUT seconds,
     /// otherwise we drop connection.
     last_client_heartbeat: Instant,
-    subs: HashMap<String, SpawnHandle>,
+    /// Subscriptions by id
+    subs: HashMap<String, Subscription>,
+    next_id: u64,
+    /// Bytes written to the context and not yet taken by the connection, the context buffers
+    /// them without limit for a client that reads slowly
+    unsent: Arc<AtomicUsize>,
 }
 
 impl SubcriptionServiceWS {
-    pub fn new(data: web::Data<AppState>) -> Self {
+    pub fn new(data: web::Data<AppState>, unsent: Arc<AtomicUsize>) -> Self {
         Self {
             data,
             last_client_heartbeat: Instant::now(),
             subs: HashMap::new(),
+            next_id: 0,
+            unsent,
         }
     }
 
-    fn subscribe(&mut self, ctx: &mut <Self as Actor>::Context) {
-        let (tx_origin, rx) = channel::unbounded();
-        let api = self.data.api.clone();
-        let tx = tx_origin.clone();
-
-        let balances_task = async move {
-            // Subscribe to (in this case, finalized) blocks.
-            let mut block_sub = api.blocks().subscribe_finalized().await.unwrap();
-            while let Some(block) = block_sub.next().await {
-                let block = block.unwrap();
-                // Ask for the events for this block.
-                let events = block.events().await.unwrap();
-
-                for event in events.iter() {
-                    let event = event.unwrap();
-
-                    if event
-                        .as_event::<sugarfunge::balances::events::Deposit>()
-                        .unwrap()
-                        .is_some()
-                    {
-                        let event = serde_json::to_string_pretty(
-                            &event
-                                .as_event::<sugarfunge::balances::events::Deposit>()
-                                .unwrap(),
-                        );
-                        if let Ok(event) = event {
-                            let event_msg = String::from("Balance Deposit: ") + &event;
-                            tx.send(event_msg).unwrap();
-                        }
-                    } else if event
-                        .as_event::<sugarfunge::balances::events::Transfer>()
-                        .unwrap()
-                        .is_some()
-                    {
-                        let event = serde_json::to_string_pretty(
-                            &event
-                                .as_event::<sugarfunge::balances::events::Transfer>()
-                                .unwrap(),
-                        );
-                        if let Ok(event) = event {
-                            let event_msg = String::from("Balance Transfer: ") + &event;
-                            tx.send(event_msg).unwrap();
-                        }
-                    }
-                }
+    fn lagging(&self) -> bool {
+        self.unsent.load(Ordering::Relaxed) > MAX_UNSENT_BYTES
+    }
+
+    /// Closes the session of a client that doesn't read its events fast enough
+    fn close_lagging(&self, ctx: &mut <Self as Actor>::Context) {
+        let message = "Events were not read fast enough, closing the connection".to_string();
+        send(
+            ctx,
+            &self.unsent,
+            SubscriptionMessage::Error { id: None, message },
+        );
+        ctx.close(None);
+        ctx.stop();
+    }
+
+    /// Adds a subscription, its events are picked from the blocks of the event bus
+    fn subscribe(&mut self, input: SubscribeInput, ctx: &mut <Self as Actor>::Context) {
+        let id = match input.id.clone() {
+            Some(id) => id,
+            None => {
+                self.next_id += 1;
+                format!("sub-{}", self.next_id)
             }
+        };
+        if self.subs.contains_key(&id) {
+            let message = format!("Subscription {} already exists", id);
+            let error = SubscriptionMessage::Error {
+                id: Some(id),
+                message,
+            };
+            return send(ctx, &self.unsent, error);
         }
-        .into_actor(self);
-
-        let api = self.data.api.clone();
-
-        let tx = tx_origin.clone();
-
-        let asset_task = async move {
-            let mut block_sub = api.blocks().subscribe_finalized().await.unwrap();
-            while let Some(block) = block_sub.next().await {
-                let block = block.unwrap();
-                // Ask for the events for this block.
-                let events = block.events().await.unwrap();
-
-                for event in events.iter() {
-                    let event = event.unwrap();
-
-                    if event
-                        .as_event::<sugarfunge::asset::events::Transferred>()
-                        .unwrap()
-                        .is_some()
-                    {
-                        let event = serde_json::to_string_pretty(
-                            &event
-                                .as_event::<sugarfunge::asset::events::Transferred>()
-                                .unwrap(),
-                        );
-                        if let Ok(event) = event {
-                            let event_msg = String::from("Asset Transferred: ") + &event;
-                            tx.send(event_msg).unwrap();
-                        }
-                    } else if event
-                        .as_event::<sugarfunge::asset::events::Mint>()
-                        .unwrap()
-                        .is_some()
-                    {
-                        let event = serde_json::to_string_pretty(
-                            &event.as_event::<sugarfunge::asset::events::Mint>().unwrap(),
-                        );
-                        if let Ok(event) = event {
-                            let event_msg = String::from("Asset Minted: ") + &event;
-                            tx.send(event_msg).unwrap();
-                        }
-                    }
-                }
+        let filter = match Filter::new(&input.topics, &input.filter) {
+            Ok(filter) => filter,
+            Err(message) => {
+                let error = SubscriptionMessage::Error {
+                    id: Some(id),
+                    message,
+                };
+                return send(ctx, &self.unsent, error);
             }
+        };
+        // Live blocks are held back while past ones are replayed
+        let held = input.from_block.map(|_| Vec::new());
+        self.subs.insert(id.clone(), Subscription { filter, held });
+        send(
+            ctx,
+            &self.unsent,
+            SubscriptionMessage::Subscribed { id: id.clone() },
+        );
+
+        if let Some(from) = input.from_block {
+            let replay = replay(self.data.clone(), ctx.address(), id.clone(), from)
+                .into_actor(self)
+                .map(move |result, act, ctx| act.replayed(id, result, ctx));
+            ctx.spawn(replay);
         }
-        .into_actor(self);
-
-        let api = self.data.api.clone();
-
-        let bag_task = async move {
-            let mut block_sub = api.blocks().subscribe_finalized().await.unwrap();
-            while let Some(block) = block_sub.next().await {
-                let block = block.unwrap();
-                // Ask for the events for this block.
-                let events = block.events().await.unwrap();
-
-                for event in events.iter() {
-                    let event = event.unwrap();
-
-                    if event
-                        .as_event::<sugarfunge::bag::events::Created>()
-                        .unwrap()
-                        .is_some()
-                    {
-                        let event = serde_json::to_string_pretty(
-                            &event
-                                .as_event::<sugarfunge::bag::events::Created>()
-                                .unwrap(),
-                        );
-                        if let Ok(event) = event {
-                            let event_msg = String::from("Bag Created: ") + &event;
-                            tx_origin.send(event_msg).unwrap();
-                        }
-                    } else if event
-                        .as_event::<sugarfunge::bag::events::Deposit>()
-                        .unwrap()
-                        .is_some()
-                    {
-                        let event = serde_json::to_string_pretty(
-                            &event
-                                .as_event::<sugarfunge::bag::events::Deposit>()
-                                .unwrap(),
-                        );
-                        if let Ok(event) = event {
-                            let event_msg = String::from("Bag Deposit: ") + &event;
-                            tx_origin.send(event_msg).unwrap();
-                        }
-                    }
+    }
+
+    /// Ends the replay of a subscription, the live blocks held back after the last replayed one
+    /// are sent and the subscription goes live
+    fn replayed(
+        &mut self,
+        id: String,
+        result: Result<u32, String>,
+        ctx: &mut <Self as Actor>::Context,
+    ) {
+        match result {
+            Ok(last) => {
+                let sub = match self.subs.get_mut(&id) {
+                    Some(sub) => sub,
+                    None => return,
+                };
+                let held = sub.held.take().unwrap_or_default();
+                for block in held.iter().filter(|block| block.number > last) {
+                    send_events(ctx, &self.unsent, &id, &sub.filter, block);
                 }
+                let replayed = SubscriptionMessage::Replayed {
+                    id,
+                    block_number: last,
+                };
+                send(ctx, &self.unsent, replayed);
+            }
+            Err(message) => {
+                self.subs.remove(&id);
+                send(
+                    ctx,
+                    &self.unsent,
+                    SubscriptionMessage::Error {
+                        id: Some(id),
+                        message,
+                    },
+                );
             }
         }
-        .into_actor(self);
-
-        let sub: SpawnHandle = ctx.spawn(balances_task);
-
-        self.subs.insert("balances_events".into(), sub);
-
-        let sub: SpawnHandle = ctx.spawn(asset_task);
-
-        self.subs.insert("asset_events".into(), sub);
-
-        let sub: SpawnHandle = ctx.spawn(bag_task);
-
-        self.subs.insert("bag_events".into(), sub);
+    }
 
-        ctx.run_interval(HEARTBEAT_INTERVAL, move |_act, ctx| {
-            if let Ok(event) = rx.try_recv() {
-                ctx.text(format!("{:#?}", event));
+    fn unsubscribe(&mut self, id: String, ctx: &mut <Self as Actor>::Context) {
+        match self.subs.remove(&id) {
+            Some(_) => send(ctx, &self.unsent, SubscriptionMessage::Unsubscribed { id }),
+            None => {
+                let message = format!("Unknown subscription {}", id);
+                send(
+                    ctx,
+                    &self.unsent,
+                    SubscriptionMessage::Error {
+                        id: Some(id),
+                        message,
+                    },
+                );
             }
-        });
+        }
     }
 
     fn heartbeat(&self, ctx: &mut <Self as Actor>::Context) {
@@ -197,7 +165,7 @@ impl SubcriptionServiceWS {
             // check client heartbeats
             if Instant::now().duration_since(act.last_client_heartbeat) > CLIENT_TIMEOUT {
                 // heartbeat timed out
-                println!("Websocket Client heartbeat failed, disconnecting!");
+                log::debug!("Websocket client heartbeat failed, disconnecting");
                 ctx.stop();
                 return;
             }
@@ -213,7 +181,31 @@ impl Actor for SubcriptionServiceWS {
     /// Method is called on actor start. We start the heartbeat process here.
     fn started(&mut self, ctx: &mut Self::Context) {
         self.heartbeat(ctx);
-        self.subscribe(ctx);
+
+        let (tx, rx) = mpsc::channel(SESSION_QUEUE);
+        self.data.events.do_send(Connect(tx));
+        ctx.add_stream(rx);
+    }
+}
+
+/// Handler for the finalized blocks of the event bus
+impl StreamHandler<Arc<BlockEvents>> for SubcriptionServiceWS {
+    /// Matching events are sent as soon as their block arrives
+    fn handle(&mut self, block: Arc<BlockEvents>, ctx: &mut Self::Context) {
+        if self.lagging() {
+            return self.close_lagging(ctx);
+        }
+        for (id, sub) in self.subs.iter_mut() {
+            match &mut sub.held {
+                Some(held) => held.push(block.clone()),
+                None => send_events(ctx, &self.unsent, id, &sub.filter, &block),
+            }
+        }
+    }
+
+    /// The bus ends the stream of a session that fell too far behind
+    fn finished(&mut self, ctx: &mut Self::Context) {
+        self.close_lagging(ctx);
     }
 }
 
@@ -221,7 +213,7 @@ impl Actor for SubcriptionServiceWS {
 impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for SubcriptionServiceWS {
     fn handle(&mut self, msg: Result<ws::Message, ws::ProtocolError>, ctx: &mut Self::Context) {
         // process websocket messages
-        println!("WS: {msg:?}");
+        log::debug!("WS: {:?}", msg);
         match msg {
             Ok(ws::Message::Ping(msg)) => {
                 self.last_client_heartbeat = Instant::now();
@@ -230,7 +222,18 @@ impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for SubcriptionServic
             Ok(ws::Message::Pong(_)) => {
                 self.last_client_heartbeat = Instant::now();
             }
-            Ok(ws::Message::Text(text)) => ctx.text(format!("echo: {}", text)),
+            Ok(ws::Message::Text(text)) => match serde_json::from_str(&text) {
+                Ok(SubscriptionRequest::Subscribe(input)) => self.subscribe(input, ctx),
+                Ok(SubscriptionRequest::Unsubscribe { id }) => self.unsubscribe(id, ctx),
+                Err(e) => send(
+                    ctx,
+                    &self.unsent,
+                    SubscriptionMessage::Error {
+                        id: None,
+                        message: format!("Invalid request: {}", e),
+                    },
+                ),
+            },
             Ok(ws::Message::Binary(bin)) => ctx.binary(bin),
             Ok(ws::Message::Close(reason)) => {
                 ctx.close(reason);
@@ -247,5 +250,105 @@ pub async fn ws(
     req: HttpRequest,
     stream: web::Payload,
 ) -> Result<HttpResponse, Error> {
-    ws::start(SubcriptionServiceWS::new(data), &req, stream)
+    let unsent = Arc::new(AtomicUsize::new(0));
+    let session = SubcriptionServiceWS::new(data, unsent.clone());
+    // The connection takes the frames as the client reads them, what it took is no longer unsent
+    let frames = ws::WebsocketContext::create(session, stream).inspect(move |frame| {
+        if let Ok(frame) = frame {
+            let _ = unsent.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |unsent| {
+                Some(unsent.saturating_sub(frame.len()))
+            });
+        }
+    });
+    Ok(ws::handshake(&req)?.streaming(frames))
+}
+
+/// Events of a block replayed for a subscription
+#[derive(Message)]
+#[rtype(result = "bool")]
+struct Replayed {
+    id: String,
+    block: BlockEvents,
+}
+
+impl Handler<Replayed> for SubcriptionServiceWS {
+    type Result = bool;
+
+    /// Sends the matching events of the block, false once the subscription is gone
+    fn handle(&mut self, msg: Replayed, ctx: &mut Self::Context) -> bool {
+        if self.lagging() {
+            self.close_lagging(ctx);
+            return false;
+        }
+        match self.subs.get(&msg.id) {
+            Some(sub) => {
+                send_events(ctx, &self.unsent, &msg.id, &sub.filter, &msg.block);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// Replays the finalized blocks from `from` to the session one at a time, returns the last one
+async fn replay(
+    data: web::Data<AppState>,
+    session: Addr<SubcriptionServiceWS>,
+    id: String,
+    from: u32,
+) -> Result<u32, String> {
+    let range = replay_range(&data, from).await?;
+    let last = *range.end();
+    for number in range {
+        let block = historical_block(&data, number).await?;
+        match session
+            .send(Replayed {
+                id: id.clone(),
+                block,
+            })
+            .await
+        {
+            Ok(true) => {}
+            _ => break,
+        }
+    }
+    Ok(last)
+}
+
+/// A subscription and, while its past events are replayed, the live blocks held back
+struct Subscription {
+    filter: Filter,
+    held: Option<Vec<Arc<BlockEvents>>>,
+}
+
+fn send_events(
+    ctx: &mut <SubcriptionServiceWS as Actor>::Context,
+    unsent: &AtomicUsize,
+    id: &str,
+    filter: &Filter,
+    block: &BlockEvents,
+) {
+    for event in block.events.iter().filter(|event| filter.matches(event)) {
+        send(
+            ctx,
+            unsent,
+            SubscriptionMessage::Event {
+                id: id.to_string(),
+                block_number: block.number,
+                block_hash: format!("{:?}", block.hash),
+                index: event.index,
+                event: event.event.clone(),
+            },
+        );
+    }
+}
+
+fn send(
+    ctx: &mut <SubcriptionServiceWS as Actor>::Context,
+    unsent: &AtomicUsize,
+    message: SubscriptionMessage,
+) {
+    let text = serde_json::to_string(&message).unwrap_or_default();
+    unsent.fetch_add(text.len(), Ordering::Relaxed);
+    ctx.text(text);
 }
@@ -0,0 +1,724 @@
+use crate::account;
+use crate::error::ApiError;
+use crate::nonce::is_nonce_error;
+use crate::proxy;
+use crate::state::*;
+use crate::util::*;
+use actix_web::{error, web, HttpResponse, HttpResponseBuilder};
+use codec::{Compact, Decode, Encode};
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::sync::RwLock;
+use std::time::Duration;
+use subxt::backend::legacy::rpc_methods::DryRunResult;
+use subxt::blocks::ExtrinsicEvents;
+use subxt::config::polkadot::PolkadotExtrinsicParamsBuilder;
+use subxt::error::{RpcError, TransactionError};
+use subxt::events::EventDetails;
+use subxt::ext::sp_core::sr25519::Pair as Sr25519Pair;
+use subxt::tx::{PairSigner, PartialExtrinsic, TxPayload, TxProgress, TxStatus};
+use subxt::utils::{AccountId32, MultiAddress, MultiSignature};
+use subxt::{OnlineClient, PolkadotConfig};
+use sugarfunge_api_types::primitives::*;
+use sugarfunge_api_types::tx::*;
+
+/// Number of async transactions whose status is kept in memory
+const MAX_TRACKED_TXS: usize = 10_000;
+
+/// Number of unsigned payloads kept waiting for their signature
+const MAX_PENDING_PAYLOADS: usize = 10_000;
+
+/// Blocks an externally signed transaction stays valid for
+const MORTAL_PERIOD: u64 = 64;
+
+/// Interval at which an unsigned payload checks whether its era has ended
+const EXPIRY_POLL: Duration = Duration::from_secs(30);
+
+/// Submissions retried with a fresh nonce after a nonce collision
+const MAX_NONCE_RETRIES: usize = 3;
+
+const CONFIRMATION_HEADER: &str = "X-Tx-Confirmation";
+const BLOCK_HASH_HEADER: &str = "X-Tx-Block-Hash";
+
+pub type Signer = PairSigner<PolkadotConfig, Sr25519Pair>;
+pub type TxEvents = ExtrinsicEvents<PolkadotConfig>;
+type Progress = TxProgress<PolkadotConfig, OnlineClient<PolkadotConfig>>;
+type Partial = PartialExtrinsic<PolkadotConfig, OnlineClient<PolkadotConfig>>;
+
+/// Who signs a transaction
+pub enum TxSigner {
+    /// A pair from the keystore or a seed
+    Pair(Signer),
+    /// A wallet outside of the api, it gets the payload to sign and submits the signature with
+    /// `tx/submit_signed`
+    External(AccountId32),
+}
+
+impl TxSigner {
+    pub fn account_id(&self) -> &AccountId32 {
+        match self {
+            TxSigner::Pair(signer) => signer.account_id(),
+            TxSigner::External(account) => account,
+        }
+    }
+}
+
+/// Per request submission options
+#[derive(Clone, Debug, Default)]
+pub struct TxOptions {
+    pub mode: TxMode,
+    pub wait_for: WaitFor,
+    pub webhook: Option<String>,
+    /// Account and balance before submission, the fees are refunded once finalized
+    pub refund: Option<(AccountId32, Balance)>,
+    /// Account the call is dispatched for, through `Proxy::proxy`
+    pub on_behalf_of: Option<Account>,
+}
+
+impl TxOptions {
+    pub fn new(params: &TxParams) -> Self {
+        Self {
+            mode: params.mode.unwrap_or_default(),
+            wait_for: params.wait_for.unwrap_or_default(),
+            webhook: params.webhook.clone(),
+            refund: None,
+            on_behalf_of: params.on_behalf_of.clone(),
+        }
+    }
+
+    pub fn refund_fees(mut self, account: &AccountId32, balance: Option<Balance>) -> Self {
+        self.refund = balance.map(|balance| (account.clone(), balance));
+        self
+    }
+}
+
+/// Events of a successful transaction and the confirmation level it reached
+pub struct TxReceipt {
+    pub events: TxEvents,
+    pub confirmation: WaitFor,
+}
+
+impl TxReceipt {
+    /// `200 OK` response builder carrying the confirmation level and block hash
+    pub fn ok(&self) -> HttpResponseBuilder {
+        let confirmation = match self.confirmation {
+            WaitFor::None => "none",
+            WaitFor::InBlock => "in_block",
+            WaitFor::Finalized => "finalized",
+        };
+        let mut response = HttpResponse::Ok();
+        response
+            .insert_header((CONFIRMATION_HEADER, confirmation))
+            .insert_header((BLOCK_HASH_HEADER, format!("{:?}", self.events.block_hash())));
+        response
+    }
+
+    /// `200 OK` response with the output of a route, the confirmation level and block hash are
+    /// in its headers and added to its json body
+    pub fn json<T: Serialize>(&self, output: T) -> HttpResponse {
+        self.ok().json(Confirmed {
+            output,
+            confirmation: self.confirmation,
+            block_hash: format!("{:?}", self.events.block_hash()),
+        })
+    }
+}
+
+pub enum Submitted {
+    /// The transaction reached the requested confirmation level successfully
+    Included(TxReceipt),
+    /// Async mode, the transaction is in the pool and tracked in the background
+    Pending(SubmittedTxOutput),
+    /// Dry run mode, the transaction was not submitted
+    DryRun(DryRunOutput),
+    /// External signer, the payload waits for its signature
+    Unsigned(UnsignedTxOutput),
+}
+
+/// Response of a write route, `included` builds it from the receipt of an included transaction
+pub fn respond<F>(submitted: Submitted, included: F) -> error::Result<HttpResponse>
+where
+    F: FnOnce(TxReceipt) -> error::Result<HttpResponse>,
+{
+    match submitted {
+        Submitted::Included(receipt) => included(receipt),
+        Submitted::Pending(output) => Ok(HttpResponse::Accepted().json(output)),
+        Submitted::DryRun(output) => Ok(HttpResponse::Ok().json(output)),
+        Submitted::Unsigned(output) => Ok(HttpResponse::Ok().json(output)),
+    }
+}
+
+/// Sign and submit a call, in blocking mode wait for the requested confirmation level. A call
+/// on behalf of another account is wrapped in `Proxy::proxy`.
+pub async fn submit<Call: TxPayload>(
+    data: &AppState,
+    call: &Call,
+    signer: &TxSigner,
+    options: TxOptions,
+) -> error::Result<Submitted> {
+    if let Some(webhook) = &options.webhook {
+        data.webhooks.check(webhook)?;
+    }
+    match &options.on_behalf_of {
+        Some(real) => {
+            let call = proxy::proxy_call(data, call, real)?;
+            submit_call(data, &call, signer, options).await
+        }
+        None => submit_call(data, call, signer, options).await,
+    }
+}
+
+async fn submit_call<Call: TxPayload>(
+    data: &AppState,
+    call: &Call,
+    signer: &TxSigner,
+    options: TxOptions,
+) -> error::Result<Submitted> {
+    let signer = match signer {
+        TxSigner::Pair(signer) => signer,
+        TxSigner::External(_) if options.mode == TxMode::DryRun => {
+            return Err(
+                ApiError::InvalidInput("Dry runs need a seed or keystore key".into()).into(),
+            )
+        }
+        TxSigner::External(account) => {
+            return unsigned(data, call, account, options)
+                .await
+                .map(Submitted::Unsigned)
+        }
+    };
+    if options.mode == TxMode::DryRun {
+        return dry_run(data, call, signer).await.map(Submitted::DryRun);
+    }
+    let account = signer.account_id().clone();
+    let progress = submit_with_nonce(data, call, signer).await?;
+    follow(data, account, progress, options).await
+}
+
+/// In blocking mode wait for the requested confirmation level of a submitted transaction,
+/// otherwise track it in the background
+async fn follow(
+    data: &AppState,
+    account: AccountId32,
+    progress: Progress,
+    options: TxOptions,
+) -> error::Result<Submitted> {
+    match (options.mode, options.wait_for) {
+        (TxMode::Blocking, WaitFor::InBlock) | (TxMode::Blocking, WaitFor::Finalized) => {
+            let result = match options.wait_for {
+                WaitFor::InBlock => wait_for_in_block(progress).await,
+                _ => progress.wait_for_finalized_success().await,
+            };
+            let events = match result {
+                Ok(events) => events,
+                Err(e) => {
+                    // The nonce was not used if the transaction left the pool
+                    if let subxt::Error::Transaction(_) = e {
+                        data.nonces.resync(&account).await;
+                    }
+                    return Err(map_subxt_err(e));
+                }
+            };
+            if options.on_behalf_of.is_some() {
+                proxy::proxied_result(data, &events)?;
+            }
+            if let Some((account, balance)) = &options.refund {
+                // The transaction went through, a failed refund doesn't fail the request
+                if let Err(e) = account::refund_fees(account, *balance).await {
+                    log::warn!("Failed to refund fees of {}: {}", account, e);
+                }
+            }
+            Ok(Submitted::Included(TxReceipt {
+                events,
+                confirmation: options.wait_for,
+            }))
+        }
+        _ => {
+            let tx_id = TxId::from(format!("{:?}", progress.extrinsic_hash()));
+            data.txs.insert(TxStatusOutput {
+                tx_id: tx_id.clone(),
+                state: TxState::Ready,
+                block_hash: None,
+                events: vec![],
+                error: None,
+            });
+            actix_web::rt::spawn(track(
+                data.clone(),
+                account,
+                tx_id.clone(),
+                progress,
+                options,
+            ));
+            Ok(Submitted::Pending(SubmittedTxOutput {
+                tx_id,
+                state: TxState::Ready,
+            }))
+        }
+    }
+}
+
+/// Weight of a call from `TransactionPaymentCallApi_query_call_info`, the most a call wrapped in
+/// another one like `Multisig::as_multi` may use
+pub async fn call_weight(data: &AppState, call_data: &[u8]) -> error::Result<TxWeight> {
+    let mut params = call_data.to_vec();
+    (call_data.len() as u32).encode_to(&mut params);
+    let info: DispatchInfo = data
+        .api
+        .runtime_api()
+        .at_latest()
+        .await
+        .map_err(map_subxt_err)?
+        .call_raw("TransactionPaymentCallApi_query_call_info", Some(&params))
+        .await
+        .map_err(map_subxt_err)?;
+    Ok(TxWeight {
+        ref_time: info.ref_time.0,
+        proof_size: info.proof_size.0,
+    })
+}
+
+/// Payload of the call for an external signer, with a nonce reserved for it and a mortal era.
+/// The payload and its options are kept until `tx/submit_signed` brings its signature, a
+/// payload dropped or still unsigned once its era ends gives its nonce back.
+async fn unsigned<Call: TxPayload>(
+    data: &AppState,
+    call: &Call,
+    account: &AccountId32,
+    options: TxOptions,
+) -> error::Result<UnsignedTxOutput> {
+    let block = data.api.blocks().at_latest().await.map_err(map_subxt_err)?;
+    let params = PolkadotExtrinsicParamsBuilder::new()
+        .mortal(block.header(), MORTAL_PERIOD)
+        .build();
+    let nonce = data
+        .nonces
+        .next(&data.rpc, account)
+        .await
+        .map_err(map_subxt_err)?;
+    let partial = match data
+        .api
+        .tx()
+        .create_partial_signed_with_nonce(call, account, nonce, params)
+    {
+        Ok(partial) => partial,
+        Err(e) => {
+            data.nonces.resync(account).await;
+            return Err(map_subxt_err(e));
+        }
+    };
+
+    let payload = partial.signer_payload();
+    let payload_id = PayloadId::from(format!("{:?}", hash(&payload)));
+    // Last block the payload can be included in
+    let expires = u64::from(block.number()) + MORTAL_PERIOD;
+    let pending = PendingPayload {
+        account: account.clone(),
+        partial,
+        options,
+    };
+    insert_payload(data, payload_id.clone(), pending).await;
+    actix_web::rt::spawn(expire(data.clone(), payload_id.clone(), expires));
+    Ok(UnsignedTxOutput {
+        payload_id,
+        payload: format!("0x{}", hex::encode(&payload)),
+        signer: Account::from(format!("{}", account)),
+        nonce,
+        era: TxEra {
+            period: MORTAL_PERIOD,
+            block_number: block.number(),
+            block_hash: format!("{:?}", block.hash()),
+        },
+        tip: Balance::from(0),
+    })
+}
+
+/// `TransactionPaymentApi_query_info` result, with a v2 weight
+#[derive(Decode)]
+struct DispatchInfo {
+    ref_time: Compact<u64>,
+    proof_size: Compact<u64>,
+    class: DispatchClass,
+    partial_fee: u128,
+}
+
+#[derive(Decode)]
+enum DispatchClass {
+    Normal,
+    Operational,
+    Mandatory,
+}
+
+/// Fee and dispatch result of the call signed with the next nonce of the signer, from the
+/// payment runtime api and `system_dryRun`. Nothing is submitted and no nonce is used up.
+async fn dry_run<Call: TxPayload>(
+    data: &AppState,
+    call: &Call,
+    signer: &Signer,
+) -> error::Result<DryRunOutput> {
+    let nonce = data
+        .rpc
+        .system_account_next_index(signer.account_id())
+        .await
+        .map_err(map_subxt_err)?;
+    let tx = data
+        .api
+        .tx()
+        .create_signed_with_nonce(call, signer, nonce, Default::default())
+        .map_err(map_subxt_err)?;
+
+    let mut params = tx.encoded().to_vec();
+    (tx.encoded().len() as u32).encode_to(&mut params);
+    let info: DispatchInfo = data
+        .api
+        .runtime_api()
+        .at_latest()
+        .await
+        .map_err(map_subxt_err)?
+        .call_raw("TransactionPaymentApi_query_info", Some(&params))
+        .await
+        .map_err(map_subxt_err)?;
+
+    let result = data
+        .rpc
+        .dry_run(tx.encoded(), None)
+        .await
+        .map_err(map_subxt_err)?
+        .into_dry_run_result(&data.api.metadata())
+        .map_err(map_subxt_err)?;
+    let error = match result {
+        DryRunResult::Success => None,
+        DryRunResult::DispatchError(e) => Some(ApiError::from(subxt::Error::Runtime(e))),
+        DryRunResult::TransactionValidityError => {
+            Some(ApiError::Transaction("Transaction is invalid".into()))
+        }
+    };
+    let class = match info.class {
+        DispatchClass::Normal => "normal",
+        DispatchClass::Operational => "operational",
+        DispatchClass::Mandatory => "mandatory",
+    };
+    Ok(DryRunOutput {
+        partial_fee: info.partial_fee.into(),
+        weight: TxWeight {
+            ref_time: info.ref_time.0,
+            proof_size: info.proof_size.0,
+        },
+        class: class.to_string(),
+        success: error.is_none(),
+        error: error.map(|e| e.details()),
+    })
+}
+
+/// Sign with the next nonce of the signer and submit, nonce collisions are
+/// retried after resyncing the nonce from chain
+async fn submit_with_nonce<Call: TxPayload>(
+    data: &AppState,
+    call: &Call,
+    signer: &Signer,
+) -> error::Result<Progress> {
+    let account = signer.account_id();
+    let mut attempt = 1;
+    loop {
+        let nonce = data
+            .nonces
+            .next(&data.rpc, account)
+            .await
+            .map_err(map_subxt_err)?;
+        let result =
+            match data
+                .api
+                .tx()
+                .create_signed_with_nonce(call, signer, nonce, Default::default())
+            {
+                Ok(tx) => tx.submit_and_watch().await,
+                Err(e) => Err(e),
+            };
+        match result {
+            Ok(progress) => return Ok(progress),
+            Err(e) => {
+                // Any rejected submission leaves the cached nonce ahead of the chain
+                data.nonces.resync(account).await;
+                if attempt >= MAX_NONCE_RETRIES || !is_nonce_error(&e) {
+                    return Err(map_subxt_err(e));
+                }
+                log::warn!("Nonce {} of {} rejected, retrying: {}", nonce, account, e);
+                attempt += 1;
+            }
+        }
+    }
+}
+
+/// Wait until the transaction is in a best block and check it succeeded
+async fn wait_for_in_block(mut progress: Progress) -> Result<TxEvents, subxt::Error> {
+    while let Some(status) = progress.next().await {
+        match status? {
+            TxStatus::InBestBlock(in_block) | TxStatus::InFinalizedBlock(in_block) => {
+                return in_block.wait_for_success().await;
+            }
+            TxStatus::Error { message } => return Err(TransactionError::Error(message).into()),
+            TxStatus::Invalid { message } => return Err(TransactionError::Invalid(message).into()),
+            TxStatus::Dropped { message } => return Err(TransactionError::Dropped(message).into()),
+            _ => continue,
+        }
+    }
+    Err(RpcError::SubscriptionDropped.into())
+}
+
+/// Follow an async transaction until it is finalized, dropped or invalid
+async fn track(
+    data: AppState,
+    account: AccountId32,
+    tx_id: TxId,
+    mut progress: Progress,
+    options: TxOptions,
+) {
+    let mut status = TxStatusOutput {
+        tx_id,
+        state: TxState::Ready,
+        block_hash: None,
+        events: vec![],
+        error: None,
+    };
+
+    while let Some(update) = progress.next().await {
+        match update {
+            Ok(TxStatus::Validated)
+            | Ok(TxStatus::Broadcasted { .. })
+            | Ok(TxStatus::NoLongerInBestBlock) => {
+                status.state = TxState::Ready;
+                status.block_hash = None;
+            }
+            Ok(TxStatus::InBestBlock(in_block)) => {
+                status.state = TxState::InBlock;
+                status.block_hash = Some(format!("{:?}", in_block.block_hash()));
+            }
+            Ok(TxStatus::InFinalizedBlock(in_block)) => {
+                status.state = TxState::Finalized;
+                status.block_hash = Some(format!("{:?}", in_block.block_hash()));
+                match in_block.fetch_events().await {
+                    Ok(events) => status.events = decode_events(&events),
+                    Err(e) => status.error = Some(e.to_string()),
+                }
+                match in_block.wait_for_success().await {
+                    Ok(events) if options.on_behalf_of.is_some() => {
+                        if let Err(e) = proxy::proxied_result(&data, &events) {
+                            status.error = Some(e.to_string());
+                        }
+                    }
+                    Ok(_) => {}
+                    Err(e) => status.error = Some(e.to_string()),
+                }
+                if let Some((account, balance)) = &options.refund {
+                    if account::refund_fees(account, *balance).await.is_err() {
+                        log::warn!("Failed to refund fees of {}", status.tx_id.as_str());
+                    }
+                }
+            }
+            Ok(TxStatus::Invalid { message }) | Ok(TxStatus::Error { message }) => {
+                status.state = TxState::Invalid;
+                status.error = Some(message);
+                data.nonces.resync(&account).await;
+            }
+            Ok(TxStatus::Dropped { message }) => {
+                status.state = TxState::Dropped;
+                status.error = Some(message);
+                data.nonces.resync(&account).await;
+            }
+            Err(e) => {
+                status.state = TxState::Dropped;
+                status.error = Some(e.to_string());
+            }
+        }
+        data.txs.insert(status.clone());
+        if let Some(webhook) = &options.webhook {
+            data.webhooks.notify(webhook, &status).await;
+        }
+        if matches!(
+            status.state,
+            TxState::Finalized | TxState::Dropped | TxState::Invalid
+        ) {
+            break;
+        }
+    }
+}
+
+pub fn decode_events(events: &TxEvents) -> Vec<TxEvent> {
+    events
+        .iter()
+        .filter_map(|event| event.ok())
+        .map(|event| decode_event(&event))
+        .collect()
+}
+
+pub fn decode_event(event: &EventDetails<PolkadotConfig>) -> TxEvent {
+    TxEvent {
+        pallet: event.pallet_name().to_string(),
+        variant: event.variant_name().to_string(),
+        fields: event
+            .field_values()
+            .ok()
+            .and_then(|fields| serde_json::to_value(fields).ok())
+            .unwrap_or_default(),
+    }
+}
+
+/// In memory status of the transactions submitted in async mode
+#[derive(Default)]
+pub struct TxTracker {
+    txs: RwLock<HashMap<String, TxStatusOutput>>,
+    order: RwLock<VecDeque<String>>,
+}
+
+impl TxTracker {
+    fn insert(&self, status: TxStatusOutput) {
+        let tx_id = String::from(&status.tx_id);
+        let mut txs = self.txs.write().unwrap();
+        if txs.insert(tx_id.clone(), status).is_none() {
+            let mut order = self.order.write().unwrap();
+            order.push_back(tx_id);
+            while order.len() > MAX_TRACKED_TXS {
+                if let Some(oldest) = order.pop_front() {
+                    txs.remove(&oldest);
+                }
+            }
+        }
+    }
+
+    pub fn get(&self, tx_id: &TxId) -> Option<TxStatusOutput> {
+        self.txs.read().unwrap().get(tx_id.as_str()).cloned()
+    }
+}
+
+struct PendingPayload {
+    account: AccountId32,
+    partial: Partial,
+    /// Options of the write request, applied when the signed payload is submitted
+    options: TxOptions,
+}
+
+/// Keeps a payload for its signature, the nonces of the payloads it pushes out are released
+async fn insert_payload(data: &AppState, payload_id: PayloadId, pending: PendingPayload) {
+    for dropped in data.payloads.insert(payload_id, pending) {
+        data.nonces.resync(&dropped.account).await;
+    }
+}
+
+/// Drops the payload once its era has ended without it being submitted. Its nonce will never be
+/// used, so the nonce of its signer is read from chain again.
+async fn expire(data: AppState, payload_id: PayloadId, expires: u64) {
+    loop {
+        actix_web::rt::time::sleep(EXPIRY_POLL).await;
+        match data.api.blocks().at_latest().await {
+            Ok(block) if u64::from(block.number()) > expires => break,
+            _ => continue,
+        }
+    }
+    if let Some(pending) = data.payloads.take(&payload_id) {
+        log::info!("Payload {} expired unsigned", payload_id.as_str());
+        data.nonces.resync(&pending.account).await;
+    }
+}
+
+/// In memory payloads built for external signers, waiting for their signature
+#[derive(Default)]
+pub struct PayloadTracker {
+    payloads: RwLock<HashMap<String, PendingPayload>>,
+    order: RwLock<VecDeque<String>>,
+}
+
+impl PayloadTracker {
+    /// Keeps the payload, returning the oldest ones dropped to make room for it
+    fn insert(&self, payload_id: PayloadId, payload: PendingPayload) -> Vec<PendingPayload> {
+        let payload_id = String::from(&payload_id);
+        let mut payloads = self.payloads.write().unwrap();
+        let mut dropped = vec![];
+        if payloads.insert(payload_id.clone(), payload).is_none() {
+            let mut order = self.order.write().unwrap();
+            order.push_back(payload_id);
+            while order.len() > MAX_PENDING_PAYLOADS {
+                if let Some(oldest) = order.pop_front() {
+                    dropped.extend(payloads.remove(&oldest));
+                }
+            }
+        }
+        dropped
+    }
+
+    fn take(&self, payload_id: &PayloadId) -> Option<PendingPayload> {
+        let payload = self.payloads.write().unwrap().remove(payload_id.as_str())?;
+        self.order
+            .write()
+            .unwrap()
+            .retain(|pending| pending != payload_id.as_str());
+        Some(payload)
+    }
+}
+
+/// Submit a transaction signed outside of the api, from the payload a write route returned for
+/// its `signer`
+pub async fn submit_signed(
+    data: web::Data<AppState>,
+    req: web::Json<SubmitSignedInput>,
+) -> error::Result<HttpResponse> {
+    if req.mode == Some(TxMode::DryRun) {
+        return Err(ApiError::InvalidInput("Signed transactions can't be dry run".into()).into());
+    }
+    let signature: [u8; 64] = hex::decode(req.signature.trim_start_matches("0x"))
+        .ok()
+        .and_then(|signature| signature.try_into().ok())
+        .ok_or_else(|| ApiError::InvalidInput("Expected a 64 byte sr25519 signature".into()))?;
+    if let Some(webhook) = &req.webhook {
+        data.webhooks.check(webhook)?;
+    }
+    let pending = data.payloads.take(&req.payload_id).ok_or_else(|| {
+        ApiError::NotFound(format!("Unknown payload {}", req.payload_id.as_str()))
+    })?;
+
+    let tx = pending.partial.sign_with_address_and_signature(
+        &MultiAddress::Id(pending.account.clone()),
+        &MultiSignature::Sr25519(signature),
+    );
+    let progress = match tx.submit_and_watch().await {
+        Ok(progress) => progress,
+        Err(e) => {
+            // Keep the payload so a bad signature can be corrected
+            insert_payload(&data, req.payload_id.clone(), pending).await;
+            return Err(map_subxt_err(e));
+        }
+    };
+
+    // The fields of the submission override the ones of the write request
+    let mut options = pending.options;
+    options.mode = req.mode.unwrap_or(options.mode);
+    options.wait_for = req.wait_for.unwrap_or(options.wait_for);
+    options.webhook = req.webhook.clone().or(options.webhook);
+    match follow(&data, pending.account, progress, options).await? {
+        Submitted::Included(receipt) => Ok(receipt.ok().json(TxStatusOutput {
+            tx_id: TxId::from(format!("{:?}", receipt.events.extrinsic_hash())),
+            state: match receipt.confirmation {
+                WaitFor::InBlock => TxState::InBlock,
+                _ => TxState::Finalized,
+            },
+            block_hash: Some(format!("{:?}", receipt.events.block_hash())),
+            events: decode_events(&receipt.events),
+            error: None,
+        })),
+        Submitted::Pending(output) => Ok(HttpResponse::Accepted().json(output)),
+        Submitted::DryRun(_) | Submitted::Unsigned(_) => {
+            Err(ApiError::Internal("Signed transaction was not submitted".into()).into())
+        }
+    }
+}
+
+/// Get the state and events of a transaction submitted in async mode
+pub async fn status(
+    data: web::Data<AppState>,
+    req: web::Json<TxStatusInput>,
+) -> error::Result<HttpResponse> {
+    match data.txs.get(&req.tx_id) {
+        Some(status) => Ok(HttpResponse::Ok().json(status)),
+        None => {
+            Err(ApiError::NotFound(format!("Unknown transaction {}", req.tx_id.as_str())).into())
+        }
+    }
+}
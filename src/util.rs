@@ -1,96 +1,108 @@
-use crate::Args;
+use crate::auth::Caller;
+use crate::cache::CacheScope;
+use crate::error::ApiError;
+use crate::keystore::KeystoreError;
+use crate::node::ConnectionState;
+use crate::tx::TxSigner;
 use crate::{config, state::AppState};
-use actix_web::{error, web, HttpResponse};
-use clap::Parser;
-use derive_more::Display;
+use actix_web::{error, web, HttpResponse, HttpResponseBuilder};
+use codec::Decode;
 use dotenv::dotenv;
+use futures::stream::{self, StreamExt, TryStreamExt};
 use serde::{Deserialize, Serialize};
-use serde_json::json;
-use subxt::error::DispatchError;
+use std::collections::HashMap;
+use std::str::FromStr;
+use subxt::blocks::Block;
+use subxt::dynamic::Value;
+use subxt::ext::scale_value::scale::decode_as_type;
 use subxt::ext::sp_core::sr25519::Pair as Sr25519Pair;
 use subxt::ext::sp_core::Pair;
+use subxt::storage::StorageAddress;
+use subxt::tx::PairSigner;
+use subxt::utils::{AccountId32, H256};
+use subxt::{OnlineClient, PolkadotConfig};
 use sugarfunge_api_types::primitives::*;
-use sugarfunge_api_types::sugarfunge::{self};
+use sugarfunge_api_types::storage_key::*;
+use sugarfunge_api_types::tx::TxParams;
 use url::Url;
 
-#[derive(Serialize, Deserialize, Debug, Display)]
-#[display(fmt = "{:?} {:?}", message, description)]
-pub struct RequestError {
-    pub message: serde_json::Value,
-    pub description: String,
-}
+const BLOCK_HASH_HEADER: &str = "X-Block-Hash";
+const BLOCK_NUMBER_HEADER: &str = "X-Block-Number";
+const CACHE_HEADER: &str = "X-Cache";
+
+pub type BlockAPI = Block<PolkadotConfig, OnlineClient<PolkadotConfig>>;
 
 // Define a struct that represents the health information you want to send.
 // Ensure it derives `Serialize`.
 #[derive(Serialize)]
 struct HealthResponse {
-    is_syncing: bool,
-    peers: u64,
-    should_have_peers: bool,
+    endpoint: String,
+    connection: ConnectionState,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    is_syncing: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    peers: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    should_have_peers: Option<bool>,
 }
 
 pub fn map_subxt_err(e: subxt::Error) -> actix_web::Error {
-    // TODO: json_err should be a json Value to improve UX
-    let json_err = json!(e.to_string());
-    let req_error = RequestError {
-        message: json_err,
-        description: "Subxt error".into(),
-    };
-    let req_error = serde_json::to_string_pretty(&req_error).unwrap();
-    error::ErrorBadRequest(req_error)
+    ApiError::from(e).into()
 }
 
-pub fn map_sf_err(e: subxt::Error) -> actix_web::Error {
-    let subxt::Error::Runtime(DispatchError::Module(module_err)) = e else {
-        return error::ErrorBadRequest("Not a Module Error");
-    };
-    let value = module_err.as_root_error::<sugarfunge::Error>().unwrap();
-    let mut json_err = json!(&format!("{:?}", value));
-
-    if let Ok(value) = module_err.details() {
-        json_err = json!(&format!(
-            "Pallet: {}, Variant: {}",
-            value.pallet.name(),
-            value.variant.name
-        ));
-    }
+pub fn map_account_err(e: sp_core::crypto::PublicError) -> actix_web::Error {
+    ApiError::InvalidAccount(format!("{:?}", e)).into()
+}
 
-    let req_error: RequestError = RequestError {
-        message: json_err,
-        description: "Sugarfunge error".into(),
-    };
-    let req_error = serde_json::to_string_pretty(&req_error).unwrap();
-    error::ErrorBadRequest(req_error)
+pub fn map_storage_key_err(e: StorageKeyError) -> actix_web::Error {
+    ApiError::Decoding(e.to_string()).into()
 }
 
-pub fn map_account_err(e: sp_core::crypto::PublicError) -> actix_web::Error {
-    let json_err: serde_json::Value = json!("Invalid account");
-    let req_error = RequestError {
-        message: json_err,
-        description: format!("{:?}", e),
-    };
-    let req_error = serde_json::to_string_pretty(&req_error).unwrap();
-    error::ErrorBadRequest(req_error)
-}
-pub fn map_try_from_slice_err(e: std::array::TryFromSliceError) -> actix_web::Error {
-    let json_err: serde_json::Value = json!("Invalid slice conversion");
-    let req_error = RequestError {
-        message: json_err,
-        description: format!("{:?}", e),
-    };
-    let req_error = serde_json::to_string_pretty(&req_error).unwrap();
-    error::ErrorBadRequest(req_error)
+pub fn map_codec_err(e: codec::Error) -> actix_web::Error {
+    ApiError::Decoding(e.to_string()).into()
 }
 
 pub fn get_pair_from_seed(seed: &Seed) -> error::Result<Sr25519Pair> {
-    Sr25519Pair::from_string(seed.as_str(), None).map_err(|e| {
-        let req_error = RequestError {
-            message: json!(&format!("{:?}", e)),
-            description: "API error".into(),
-        };
-        let req_error = serde_json::to_string_pretty(&req_error).unwrap();
-        error::ErrorBadRequest(req_error)
-    })
+    Sr25519Pair::from_string(seed.as_str(), None)
+        .map_err(|e| ApiError::InvalidSeed(format!("{:?}", e)).into())
+}
+
+pub fn map_keystore_err(e: KeystoreError) -> actix_web::Error {
+    ApiError::from(e).into()
+}
+
+/// Resolve the signing pair of a request, a keystore key takes precedence
+/// over a raw seed, which is only accepted if allowed by the server. A keystore
+/// key only signs for the callers it is bound to.
+pub fn get_pair(
+    data: &AppState,
+    caller: &Caller,
+    seed: &Option<Seed>,
+    key: &Option<KeyId>,
+) -> error::Result<Sr25519Pair> {
+    if let Some(key) = key {
+        return data.keystore.pair(key, caller).map_err(map_keystore_err);
+    }
+    let e = match seed {
+        Some(seed) if data.allow_seed => return get_pair_from_seed(seed),
+        Some(_) => ApiError::Forbidden(
+            "Raw seeds are disabled, sign with a keystore key instead".to_string(),
+        ),
+        None => ApiError::InvalidInput("Either a seed or a keystore key is required".to_string()),
+    };
+    Err(e.into())
+}
+
+/// Resolve the signer of a request, an external `signer` account gets the payload to sign back
+/// instead of a submitted transaction
+pub fn get_signer(data: &AppState, caller: &Caller, params: &TxParams) -> error::Result<TxSigner> {
+    match &params.signer {
+        Some(account) => AccountId32::try_from(account)
+            .map(TxSigner::External)
+            .map_err(map_account_err),
+        None => get_pair(data, caller, &params.seed, &params.key)
+            .map(|pair| TxSigner::Pair(PairSigner::new(pair))),
+    }
 }
 
 pub fn _url_to_string(url: Url) -> String {
@@ -104,64 +116,254 @@ pub fn _url_to_string(url: Url) -> String {
     }
 }
 
-pub fn map_fula_err(e: subxt::Error) -> actix_web::Error {
-    let subxt::Error::Runtime(DispatchError::Module(module_err)) = e else {
-        return error::ErrorBadRequest("Not a Module Error");
+pub async fn health_check(data: web::Data<AppState>) -> error::Result<HttpResponse> {
+    let rpc = &data.rpc;
+    let health = rpc.system_health().await;
+    // Read the state after the call, which may have failed over to another endpoint
+    let (endpoint, connection) = data.node.status();
+
+    // Map the fields from SystemHealth to HealthResponse
+    let health_response = HealthResponse {
+        endpoint: endpoint.to_string(),
+        connection,
+        is_syncing: health.as_ref().ok().map(|health| health.is_syncing),
+        peers: health.as_ref().ok().map(|health| health.peers as u64),
+        should_have_peers: health.as_ref().ok().map(|health| health.should_have_peers),
     };
-    let value = module_err.as_root_error::<sugarfunge::Error>().unwrap();
-    let mut json_err = json!(&format!("{:?}", value));
-
-    if let Ok(value) = module_err.details() {
-        json_err = json!(&format!(
-            "Pallet: {}, Variant: {}",
-            value.pallet.name(),
-            value.variant.name
-        ));
+
+    // Respond with the serialized HealthResponse, unavailable while no endpoint is reachable
+    match health {
+        Ok(_) => Ok(HttpResponse::Ok().json(health_response)),
+        Err(_) => Ok(HttpResponse::ServiceUnavailable().json(health_response)),
     }
+}
 
-    let req_error: RequestError = RequestError {
-        message: json_err,
-        description: "Fula Pallet error".into(),
+/// Block a read is served from, the latest one unless `at` is set
+pub async fn block_at(data: &AppState, at: &Option<BlockAt>) -> error::Result<BlockAPI> {
+    let hash = match at {
+        None => match data.cache.head() {
+            Some(head) => head,
+            None => return data.api.blocks().at_latest().await.map_err(map_subxt_err),
+        },
+        Some(BlockAt::Hash(hash)) => H256::from_str(hash)
+            .map_err(|_| ApiError::InvalidInput(format!("Invalid block hash {}", hash)))?,
+        Some(BlockAt::Number(number)) => data
+            .rpc
+            .chain_get_block_hash(Some((*number).into()))
+            .await
+            .map_err(map_subxt_err)?
+            .ok_or_else(|| ApiError::NotFound(format!("Block {} not found", number)))?,
     };
-    let req_error = serde_json::to_string_pretty(&req_error).unwrap();
-    error::ErrorBadRequest(req_error)
+    data.api.blocks().at(hash).await.map_err(map_subxt_err)
 }
-pub fn map_fula_pool_err(e: subxt::Error) -> actix_web::Error {
-    let subxt::Error::Runtime(DispatchError::Module(module_err)) = e else {
-        return error::ErrorBadRequest("Not a Module Error");
-    };
-    let value = module_err.as_root_error::<sugarfunge::Error>().unwrap();
-    let mut json_err = json!(&format!("{:?}", value));
-
-    if let Ok(value) = module_err.details() {
-        json_err = json!(&format!(
-            "Pallet: {}, Variant: {}",
-            value.pallet.name(),
-            value.variant.name
-        ));
+
+/// `200 OK` response builder echoing the block a read was served from
+pub fn ok_at(block: &BlockAPI) -> HttpResponseBuilder {
+    let mut response = HttpResponse::Ok();
+    response
+        .insert_header((BLOCK_HASH_HEADER, format!("{:?}", block.hash())))
+        .insert_header((BLOCK_NUMBER_HEADER, block.number().to_string()));
+    response
+}
+
+/// `ok_at` along with the `X-Cache` header of a cached route
+pub fn ok_cached(block: &BlockAPI, cache: &Option<CacheScope<'_>>) -> HttpResponseBuilder {
+    let mut response = ok_at(block);
+    if let Some(cache) = cache {
+        response.insert_header((CACHE_HEADER, cache.status()));
     }
+    response
+}
 
-    let req_error: RequestError = RequestError {
-        message: json_err,
-        description: "Fula Pool error".into(),
+/// Most keys requested from the node in a single `state_getKeysPaged` call
+const MAX_PAGE_SIZE: u32 = 1000;
+
+/// Most values requested from the node in a single `state_queryStorageAt` call
+const VALUES_BATCH_SIZE: usize = 100;
+
+/// Most `state_queryStorageAt` calls in flight for a single request
+const VALUES_CONCURRENCY: usize = 8;
+
+/// Storage keys under `prefix` at `block`, resuming after `cursor`.
+/// Without a `limit` every key is returned, fetched page by page. With one, at most `limit` keys
+/// are returned along with the cursor of the next page if there may be more.
+/// Pages are read through `cache` when the route is cached.
+pub async fn fetch_keys_page(
+    data: &AppState,
+    block: &BlockAPI,
+    cache: Option<&CacheScope<'_>>,
+    prefix: Vec<u8>,
+    limit: Option<u32>,
+    cursor: &Option<Cursor>,
+) -> error::Result<(Vec<Vec<u8>>, Option<Cursor>)> {
+    let mut start_key = match cursor {
+        Some(cursor) => {
+            let invalid = || ApiError::InvalidInput(format!("Invalid cursor {}", cursor.as_str()));
+            let key =
+                hex::decode(cursor.as_str().trim_start_matches("0x")).map_err(|_| invalid())?;
+            if !key.starts_with(&prefix) {
+                return Err(invalid().into());
+            }
+            Some(key)
+        }
+        None => None,
     };
-    let req_error = serde_json::to_string_pretty(&req_error).unwrap();
-    error::ErrorBadRequest(req_error)
+    let hash = block.hash();
+    let mut keys = Vec::new();
+    loop {
+        let count = match limit {
+            Some(limit) => (limit - keys.len() as u32).min(MAX_PAGE_SIZE),
+            None => MAX_PAGE_SIZE,
+        };
+        if count == 0 {
+            break;
+        }
+        let cached = cache.and_then(|cache| cache.page(hash, &prefix, start_key.as_deref(), count));
+        let page = match cached {
+            Some(page) => page,
+            None => {
+                let page = data
+                    .rpc
+                    .state_get_keys_paged(&prefix, count, start_key.as_deref(), Some(hash))
+                    .await
+                    .map_err(map_subxt_err)?;
+                if let Some(cache) = cache {
+                    cache.insert_page(hash, &prefix, start_key.as_deref(), count, page.clone());
+                }
+                page
+            }
+        };
+        let exhausted = page.len() < count as usize;
+        if let Some(last) = page.last() {
+            start_key = Some(last.clone());
+        }
+        keys.extend(page);
+        if exhausted {
+            return Ok((keys, None));
+        }
+    }
+    let next_cursor = start_key.map(|key| Cursor::from(format!("0x{}", hex::encode(key))));
+    Ok((keys, next_cursor))
 }
 
-pub async fn health_check(data: web::Data<AppState>) -> error::Result<HttpResponse> {
-    let rpc = &data.rpc;
-    let health = rpc.system_health().await.map_err(map_subxt_err)?;
+/// Key decoder for the storage map `address` points at, laid out from the runtime metadata
+pub fn key_decoder<A: StorageAddress>(
+    data: &AppState,
+    address: &A,
+) -> error::Result<StorageKeyDecoder> {
+    StorageKeyDecoder::new(&data.api.metadata(), address).map_err(map_storage_key_err)
+}
 
-    // Map the fields from SystemHealth to HealthResponse
-    let health_response = HealthResponse {
-        is_syncing: health.is_syncing,
-        peers: health.peers as u64,
-        should_have_peers: health.should_have_peers,
-    };
+/// Values stored under `keys` at `block`, in key order, skipping the keys without a value.
+/// Keys missing from `cache` are queried in batches, a few batches at a time.
+pub async fn fetch_values(
+    data: &AppState,
+    block: &BlockAPI,
+    cache: Option<&CacheScope<'_>>,
+    keys: Vec<Vec<u8>>,
+) -> error::Result<Vec<(Vec<u8>, Vec<u8>)>> {
+    let hash = block.hash();
+    let mut values = HashMap::new();
+    let mut misses = Vec::new();
+    for key in keys.iter() {
+        match cache.and_then(|cache| cache.value(hash, key)) {
+            Some(value) => {
+                values.insert(key.clone(), value);
+            }
+            None => misses.push(key.clone()),
+        }
+    }
+    let batches: Vec<Vec<_>> = stream::iter(misses.chunks(VALUES_BATCH_SIZE))
+        .map(|batch| async move {
+            let change_sets = data
+                .rpc
+                .state_query_storage_at(batch.iter().map(|key| key.as_slice()), Some(hash))
+                .await?;
+            let mut changes: HashMap<Vec<u8>, Vec<u8>> = change_sets
+                .into_iter()
+                .flat_map(|change_set| change_set.changes)
+                .filter_map(|(key, value)| Some((key.0, value?.0)))
+                .collect();
+            Ok::<_, subxt::Error>(
+                batch
+                    .iter()
+                    .map(|key| (key.clone(), changes.remove(key)))
+                    .collect::<Vec<_>>(),
+            )
+        })
+        .buffered(VALUES_CONCURRENCY)
+        .try_collect()
+        .await
+        .map_err(map_subxt_err)?;
+    for (key, value) in batches.into_iter().flatten() {
+        if let Some(cache) = cache {
+            cache.insert_value(hash, key.clone(), value.clone());
+        }
+        values.insert(key, value);
+    }
+    Ok(keys
+        .into_iter()
+        .filter_map(|key| {
+            let value = values.remove(&key)??;
+            Some((key, value))
+        })
+        .collect())
+}
+
+/// Decoded value `address` points at, read through `cache` when the route is cached
+pub async fn fetch_value<A>(
+    data: &AppState,
+    block: &BlockAPI,
+    cache: Option<&CacheScope<'_>>,
+    address: &A,
+) -> error::Result<Option<A::Target>>
+where
+    A: StorageAddress,
+    A::Target: Decode,
+{
+    let key = data
+        .api
+        .storage()
+        .address_bytes(address)
+        .map_err(map_subxt_err)?;
+    match fetch_values(data, block, cache, vec![key]).await?.pop() {
+        Some((_, value)) => A::Target::decode(&mut &value[..])
+            .map(Some)
+            .map_err(|e| ApiError::Decoding(e.to_string()).into()),
+        None => Ok(None),
+    }
+}
+
+/// Like `fetch_keys_page`, along with the value stored under every key
+pub async fn fetch_entries_page(
+    data: &AppState,
+    block: &BlockAPI,
+    cache: Option<&CacheScope<'_>>,
+    prefix: Vec<u8>,
+    limit: Option<u32>,
+    cursor: &Option<Cursor>,
+) -> error::Result<(Vec<(Vec<u8>, Vec<u8>)>, Option<Cursor>)> {
+    let (keys, next_cursor) = fetch_keys_page(data, block, cache, prefix, limit, cursor).await?;
+    let entries = fetch_values(data, block, cache, keys).await?;
+    Ok((entries, next_cursor))
+}
 
-    // Respond with the serialized HealthResponse
-    Ok(HttpResponse::Ok().json(health_response))
+/// Call data decoded against the runtime call type of the live metadata, to be the argument of a
+/// call that dispatches another one like `Proxy::proxy` or `Multisig::as_multi`
+pub fn call_value(data: &AppState, call_data: &[u8]) -> error::Result<Value> {
+    let metadata = data.api.metadata();
+    let call = decode_as_type(
+        &mut &call_data[..],
+        metadata.outer_enums().call_enum_ty(),
+        metadata.types(),
+    )
+    .map_err(|e| ApiError::Decoding(e.to_string()))?;
+    Ok(call.remove_context())
+}
+
+/// `AccountId32` as a dynamic value, a composite around its bytes
+pub fn account_value(account: &AccountId32) -> Value {
+    Value::unnamed_composite([Value::from_bytes(account.0)])
 }
 
 // Function to get the hash using the blake2_256 of a [u8] value
@@ -173,7 +375,7 @@ fn endpoint(host: String, cmd: &'static str) -> String {
     format!("{}{}", host.as_str(), cmd)
 }
 
-async fn req<'a, I, O>(host: String, cmd: &'static str, args: I) -> Result<O, RequestError>
+async fn req<'a, I, O>(host: String, cmd: &'static str, args: I) -> Result<O, ApiError>
 where
     I: Serialize,
     O: for<'de> Deserialize<'de>,
@@ -187,40 +389,21 @@ where
     match sf_res {
         Ok(res) => {
             if let Err(err) = res.error_for_status_ref() {
-                match res.json::<RequestError>().await {
-                    Ok(err) => Err(err),
-                    Err(_) => Err(RequestError {
-                        message: json!(format!("{:#?}", err)),
-                        description: "Reqwest json error.".into(),
-                    }),
+                match res.json::<serde_json::Value>().await {
+                    Ok(body) => Err(ApiError::Upstream(format!("{}: {}", err, body))),
+                    Err(_) => Err(ApiError::Upstream(err.to_string())),
                 }
             } else {
-                match res.json().await {
-                    Ok(res) => Ok(res),
-                    Err(err) => Err(RequestError {
-                        message: json!(format!("{:#?}", err)),
-                        description: "Reqwest json error.".into(),
-                    }),
-                }
+                res.json()
+                    .await
+                    .map_err(|err| ApiError::Upstream(format!("Invalid response: {}", err)))
             }
         }
-        Err(err) => Err(RequestError {
-            message: json!(format!("{:#?}", err)),
-            description: "Reqwest error.".into(),
-        }),
+        Err(err) => Err(ApiError::Upstream(err.to_string())),
     }
 }
 
-pub async fn fula_sugarfunge_req<'a, I, O>(cmd: &'static str, args: I) -> Result<O, RequestError>
-where
-    I: Serialize,
-    O: for<'de> Deserialize<'de>,
-{
-    let env = Args::parse();
-    req(env.listen.into(), cmd, args).await
-}
-
-pub async fn fula_contract_req<'a, I, O>(cmd: &'static str, args: I) -> Result<O, RequestError>
+pub async fn fula_contract_req<'a, I, O>(cmd: &'static str, args: I) -> Result<O, ApiError>
 where
     I: Serialize,
     O: for<'de> Deserialize<'de>,
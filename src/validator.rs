@@ -1,11 +1,12 @@
 use std::str::FromStr;
 
+use crate::auth::Caller;
+use crate::error::ApiError;
 use crate::state::*;
+use crate::tx::{respond, submit, TxOptions};
 use crate::util::*;
 use actix_web::{error, web, HttpResponse};
-use serde_json::json;
 // TO DO: Here is using the exporting from the dependencies like in the sugarfunge-node is done
-use subxt::tx::PairSigner;
 use sugarfunge_api_types::primitives::*;
 use sugarfunge_api_types::sugarfunge;
 // TO DO: Here is the exported of the session keys type needed for the set_keys function
@@ -14,10 +15,10 @@ use sugarfunge_api_types::validator::*;
 
 pub async fn add_validator(
     data: web::Data<AppState>,
+    caller: Caller,
     req: web::Json<AddValidatorInput>,
 ) -> error::Result<HttpResponse> {
-    let pair = get_pair_from_seed(&req.seed)?;
-    let signer = PairSigner::new(pair);
+    let signer = get_signer(&data, &caller, &req.tx)?;
     let validator_public =
         sp_core::sr25519::Public::from_str(req.validator_id.as_str()).map_err(map_account_err)?;
     let validator_bytes: [u8; 32] = validator_public.0; // Convert Public key to a byte array
@@ -26,80 +27,69 @@ pub async fn add_validator(
         validator_id,
     };
     let call = sugarfunge::runtime_types::sugarfunge_runtime::RuntimeCall::ValidatorSet(call);
-    let api = &data.api;
-
     let call_value = sugarfunge::tx().sudo().sudo(call);
 
-    let result = api
-        .tx()
-        .sign_and_submit_then_watch(&call_value, &signer, Default::default())
-        .await
-        .map_err(map_subxt_err)?
-        .wait_for_finalized_success()
-        .await
-        .map_err(map_sf_err)?;
-
-    let result = result
-        .find_first::<sugarfunge::validator_set::events::ValidatorAdditionInitiated>()
-        .map_err(map_subxt_err)?;
-
-    match result {
-        Some(event) => Ok(HttpResponse::Ok().json(AddValidatorOutput {
-            validator_id: ValidatorId::from(event.0.to_string()),
-        })),
-        None => Ok(HttpResponse::BadRequest().json(RequestError {
-            message: json!("Failed to find sugarfunge::validator::events::AddValidator"),
-            description: String::new(),
-        })),
-    }
+    let options = TxOptions::new(&req.tx);
+    respond(
+        submit(&data, &call_value, &signer, options).await?,
+        |receipt| {
+            let result = receipt
+                .events
+                .find_first::<sugarfunge::validator_set::events::ValidatorAdditionInitiated>()
+                .map_err(map_subxt_err)?;
+
+            match result {
+                Some(event) => Ok(receipt.json(AddValidatorOutput {
+                    validator_id: ValidatorId::from(event.0.to_string()),
+                })),
+                None => Err(ApiError::EventNotFound(
+                    "sugarfunge::validator::events::AddValidator".into(),
+                )
+                .into()),
+            }
+        },
+    )
 }
 
 pub async fn activate_validator(
     data: web::Data<AppState>,
+    caller: Caller,
     req: web::Json<AddValidatorInput>,
 ) -> error::Result<HttpResponse> {
-    let pair = get_pair_from_seed(&req.seed)?;
-    let signer = PairSigner::new(pair);
+    let signer = get_signer(&data, &caller, &req.tx)?;
     let validator_public =
         sp_core::sr25519::Public::from_str(req.validator_id.as_str()).map_err(map_account_err)?;
     let validator_bytes: [u8; 32] = validator_public.0; // Convert Public key to a byte array
     let validator_id = subxt::utils::AccountId32::from(validator_bytes); // Create AccountId32 from the byte array
-    let api = &data.api;
-
     let call = sugarfunge::tx()
         .validator_set()
         .add_validator_again(validator_id);
 
-    let result = api
-        .tx()
-        .sign_and_submit_then_watch(&call, &signer, Default::default())
-        .await
-        .map_err(map_subxt_err)?
-        .wait_for_finalized_success()
-        .await
-        .map_err(map_sf_err)?;
-
-    let result = result
-        .find_first::<sugarfunge::validator_set::events::ValidatorAdditionInitiated>()
-        .map_err(map_subxt_err)?;
-
-    match result {
-        Some(event) => Ok(HttpResponse::Ok().json(AddValidatorOutput {
-            validator_id: ValidatorId::from(event.0.to_string()),
-        })),
-        None => Ok(HttpResponse::BadRequest().json(RequestError {
-            message: json!("Failed to find sugarfunge::validator::events::ActivateValidator"),
-            description: String::new(),
-        })),
-    }
+    let options = TxOptions::new(&req.tx);
+    respond(submit(&data, &call, &signer, options).await?, |receipt| {
+        let result = receipt
+            .events
+            .find_first::<sugarfunge::validator_set::events::ValidatorAdditionInitiated>()
+            .map_err(map_subxt_err)?;
+
+        match result {
+            Some(event) => Ok(receipt.json(AddValidatorOutput {
+                validator_id: ValidatorId::from(event.0.to_string()),
+            })),
+            None => Err(ApiError::EventNotFound(
+                "sugarfunge::validator::events::ActivateValidator".into(),
+            )
+            .into()),
+        }
+    })
 }
 
 pub async fn remove_validator(
     data: web::Data<AppState>,
+    caller: Caller,
     req: web::Json<RemoveValidatorInput>,
 ) -> error::Result<HttpResponse> {
-    let pair = get_pair_from_seed(&req.seed)?;
-    let signer = PairSigner::new(pair);
+    let signer = get_signer(&data, &caller, &req.tx)?;
     let validator_public =
         sp_core::sr25519::Public::from_str(req.validator_id.as_str()).map_err(map_account_err)?;
     let validator_bytes: [u8; 32] = validator_public.0; // Convert Public key to a byte array
@@ -114,45 +104,40 @@ pub async fn remove_validator(
         };
     let call = sugarfunge::runtime_types::sugarfunge_runtime::RuntimeCall::ValidatorSet(call);
     let call_value = sugarfunge::tx().sudo().sudo(call);
-    let api = &data.api;
-
-    let result = api
-        .tx()
-        .sign_and_submit_then_watch(&call_value, &signer, Default::default())
-        .await
-        .map_err(map_subxt_err)?
-        .wait_for_finalized_success()
-        .await
-        .map_err(map_sf_err)?;
-
-    let result = result
-        .find_first::<sugarfunge::validator_set::events::ValidatorRemovalInitiated>()
-        .map_err(map_subxt_err)?;
-
-    match result {
-        Some(event) => Ok(HttpResponse::Ok().json(RemoveValidatorOutput {
-            validator_id: ValidatorId::from(event.0.to_string()),
-        })),
-        None => Ok(HttpResponse::BadRequest().json(RequestError {
-            message: json!("Failed to find sugarfunge::validator::events::RemoveValidator"),
-            description: String::new(),
-        })),
-    }
+    let options = TxOptions::new(&req.tx);
+    respond(
+        submit(&data, &call_value, &signer, options).await?,
+        |receipt| {
+            let result = receipt
+                .events
+                .find_first::<sugarfunge::validator_set::events::ValidatorRemovalInitiated>()
+                .map_err(map_subxt_err)?;
+
+            match result {
+                Some(event) => Ok(receipt.json(RemoveValidatorOutput {
+                    validator_id: ValidatorId::from(event.0.to_string()),
+                })),
+                None => Err(ApiError::EventNotFound(
+                    "sugarfunge::validator::events::RemoveValidator".into(),
+                )
+                .into()),
+            }
+        },
+    )
 }
 
 pub async fn set_keys(
     data: web::Data<AppState>,
+    caller: Caller,
     req: web::Json<SetKeysInput>,
 ) -> error::Result<HttpResponse> {
-    let pair = get_pair_from_seed(&req.seed)?;
-    let signer = PairSigner::new(pair);
+    let signer = get_signer(&data, &caller, &req.tx)?;
 
     let aura_public =
         sp_core::sr25519::Public::from_str(req.aura.as_str()).map_err(map_account_err)?;
     let grandpa_public =
         sp_core::sr25519::Public::from_str(req.grandpa.as_str()).map_err(map_account_err)?;
 
-    let api = &data.api;
     let aura: sugarfunge_api_types::sugarfunge::runtime_types::sp_consensus_aura::sr25519::app_sr25519::Public = unsafe { std::mem::transmute(aura_public.clone()) };
     let grandpa: sugarfunge_api_types::sugarfunge::runtime_types::sp_consensus_grandpa::app::Public = unsafe { std::mem::transmute(grandpa_public) };
     let im_online: sugarfunge_api_types::sugarfunge::runtime_types::pallet_im_online::sr25519::app_sr25519::Public = unsafe { std::mem::transmute(aura_public.clone()) };
@@ -167,18 +152,13 @@ pub async fn set_keys(
         .session()
         .set_keys(session_keys, "0x".into());
 
-    let _result = api
-        .tx()
-        .sign_and_submit_then_watch(&call, &signer, Default::default())
-        .await
-        .map_err(map_subxt_err)?
-        .wait_for_finalized_success()
-        .await
-        .map_err(map_sf_err)?;
-    Ok(HttpResponse::Ok().json(SetKeysOutput {
-        aura: req.aura.clone(),
-        grandpa: req.grandpa.clone(),
-    }))
+    let options = TxOptions::new(&req.tx);
+    respond(submit(&data, &call, &signer, options).await?, |receipt| {
+        Ok(receipt.json(SetKeysOutput {
+            aura: req.aura.clone(),
+            grandpa: req.grandpa.clone(),
+        }))
+    })
 }
 
 pub async fn is_validator(
@@ -198,10 +178,10 @@ pub async fn is_validator(
 
     // Convert [u8; 32] array to subxt::utils::AccountId32
     let account = subxt::utils::AccountId32::from(account_array);
-    let api = &data.api;
 
     let call = sugarfunge::storage().validator_set().approved_validators();
-    let storage = api.storage().at_latest().await.map_err(map_subxt_err)?;
+    let block = block_at(&data, &req.at).await?;
+    let storage = block.storage();
     let approved_list = storage.fetch(&call).await.map_err(map_subxt_err)?;
 
     match approved_list {
@@ -210,12 +190,11 @@ pub async fn is_validator(
     }
 
     let call = sugarfunge::storage().validator_set().validators();
-    let storage = api.storage().at_latest().await.map_err(map_subxt_err)?;
     let offline_list = storage.fetch(&call).await.map_err(map_subxt_err)?;
 
     match offline_list {
         Some(validators) => result.offline = !validators.0.contains(&account),
         None => result.offline = true,
     }
-    Ok(HttpResponse::Ok().json(result))
+    Ok(ok_at(&block).json(result))
 }
@@ -0,0 +1,165 @@
+use crate::error::ApiError;
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use std::error::Error;
+use std::net::{IpAddr, SocketAddr, ToSocketAddrs};
+use std::sync::Arc;
+use std::time::Duration;
+use sugarfunge_api_types::tx::TxStatusOutput;
+use url::{Host, Url};
+
+/// Time a webhook has to answer a status change
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Posts the status changes of async transactions to the webhooks of their write requests
+pub struct Webhooks {
+    client: reqwest::Client,
+    /// Hosts webhooks may point to, any host with a public address when empty
+    hosts: Vec<String>,
+}
+
+impl Webhooks {
+    pub fn new(hosts: Vec<String>) -> reqwest::Result<Self> {
+        let mut client = reqwest::Client::builder()
+            .timeout(TIMEOUT)
+            .redirect(reqwest::redirect::Policy::none());
+        if hosts.is_empty() {
+            client = client.dns_resolver(Arc::new(PublicResolver));
+        }
+        Ok(Self {
+            client: client.build()?,
+            hosts,
+        })
+    }
+
+    /// Refuses webhooks that aren't http(s), or whose host isn't allowed. Without allowed hosts,
+    /// loopback, private and link local addresses are refused, names resolving to them included.
+    pub fn check(&self, webhook: &str) -> Result<Url, ApiError> {
+        let invalid =
+            |reason: &str| ApiError::InvalidInput(format!("Webhook {}: {}", webhook, reason));
+        let url = Url::parse(webhook).map_err(|e| invalid(&e.to_string()))?;
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err(invalid("expected an http or https url"));
+        }
+        let allowed = match url.host() {
+            _ if !self.hosts.is_empty() => {
+                let host = url.host_str().unwrap_or_default();
+                self.hosts
+                    .iter()
+                    .any(|allowed| allowed.eq_ignore_ascii_case(host))
+            }
+            Some(Host::Ipv4(ip)) => is_public(IpAddr::V4(ip)),
+            Some(Host::Ipv6(ip)) => is_public(IpAddr::V6(ip)),
+            Some(Host::Domain(domain)) => domain != "localhost" && !domain.ends_with(".localhost"),
+            None => false,
+        };
+        if !allowed {
+            return Err(invalid("host not allowed"));
+        }
+        Ok(url)
+    }
+
+    pub async fn notify(&self, webhook: &str, status: &TxStatusOutput) {
+        let result = match self.check(webhook) {
+            Ok(url) => self.client.post(url).json(status).send().await,
+            Err(e) => {
+                log::warn!("{}", e);
+                return;
+            }
+        };
+        if let Err(e) = result.and_then(|res| res.error_for_status()) {
+            log::warn!(
+                "Webhook {} failed for {}: {}",
+                webhook,
+                status.tx_id.as_str(),
+                e
+            );
+        }
+    }
+}
+
+/// Resolves webhook hosts to their public addresses only
+struct PublicResolver;
+
+impl Resolve for PublicResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        Box::pin(public_addrs(name.as_str().to_string()))
+    }
+}
+
+async fn public_addrs(host: String) -> Result<Addrs, Box<dyn Error + Send + Sync>> {
+    let lookup = host.clone();
+    let addrs = tokio::task::spawn_blocking(move || (lookup.as_str(), 0).to_socket_addrs())
+        .await??
+        .filter(|addr| is_public(addr.ip()))
+        .collect::<Vec<SocketAddr>>();
+    if addrs.is_empty() {
+        return Err(format!("{} has no public address", host).into());
+    }
+    Ok(Box::new(addrs.into_iter()))
+}
+
+/// Whether ip is routable on the internet, so a webhook can't reach the api host or its network
+fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, ..] = ip.octets();
+            let shared = a == 100 && (64..128).contains(&b);
+            !(ip.is_private()
+                || ip.is_loopback()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || ip.is_documentation()
+                || shared
+                || a == 0)
+        }
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_public(IpAddr::V4(ip)),
+            None => {
+                let unique_local = ip.segments()[0] & 0xfe00 == 0xfc00;
+                let link_local = ip.segments()[0] & 0xffc0 == 0xfe80;
+                !(ip.is_loopback() || ip.is_unspecified() || unique_local || link_local)
+            }
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn refuses_internal_hosts() {
+        let webhooks = Webhooks::new(vec![]).unwrap();
+        assert!(webhooks.check("https://hooks.example.com/tx").is_ok());
+        assert!(webhooks.check("http://93.184.216.34:8080/tx").is_ok());
+        for webhook in [
+            "ftp://hooks.example.com/tx",
+            "file:///etc/passwd",
+            "http://localhost:4000/tx",
+            "http://api.localhost/tx",
+            "http://127.0.0.1/tx",
+            "http://10.0.0.8/tx",
+            "http://192.168.1.1/tx",
+            "http://169.254.169.254/latest/meta-data",
+            "http://100.64.0.1/tx",
+            "http://0.0.0.0/tx",
+            "http://[::1]/tx",
+            "http://[fd00::1]/tx",
+            "http://[fe80::1]/tx",
+            "http://[::ffff:127.0.0.1]/tx",
+        ] {
+            assert!(webhooks.check(webhook).is_err(), "{} was allowed", webhook);
+        }
+    }
+
+    #[test]
+    fn allowed_hosts_replace_the_address_checks() {
+        let webhooks = Webhooks::new(vec!["hooks.internal".into(), "10.0.0.8".into()]).unwrap();
+        assert!(webhooks.check("http://hooks.internal/tx").is_ok());
+        assert!(webhooks.check("http://HOOKS.internal:8080/tx").is_ok());
+        assert!(webhooks.check("http://10.0.0.8/tx").is_ok());
+        assert!(webhooks.check("https://hooks.example.com/tx").is_err());
+        assert!(webhooks.check("http://hooks.internal.evil.com/tx").is_err());
+    }
+}